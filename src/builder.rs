@@ -0,0 +1,257 @@
+//! Generation of peripheral access code from build scripts
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::config::{
+    AtomicsBackend, Backend, Config, IdentFormat, IdentFormatsTheme, SourceType, Target,
+};
+
+/// Generates peripheral access code for a device from a `build.rs` script.
+///
+/// The result is a single file in `OUT_DIR` without crate attributes, which can be included
+/// into the device crate with `include!`:
+///
+/// ```ignore
+/// // build.rs
+/// fn main() {
+///     svd2rust::Builder::new("STM32F30x.svd")
+///         .target(svd2rust::Target::CortexM)
+///         .atomics(true)
+///         .build()
+///         .unwrap();
+/// }
+/// ```
+///
+/// ```ignore
+/// // src/lib.rs
+/// #![no_std]
+/// #![allow(non_camel_case_types)]
+/// #![allow(non_snake_case)]
+/// include!(concat!(env!("OUT_DIR"), "/stm32f30x.rs"));
+/// ```
+///
+/// `cargo:rerun-if-changed` lines are printed for the SVD, settings, included and patch files.
+/// For targets that use `device.x`, it is placed in `OUT_DIR` and added to the linker search path
+/// when the `rt` feature of the crate is enabled.
+#[derive(Clone, Debug)]
+pub struct Builder {
+    input: PathBuf,
+    out_dir: Option<PathBuf>,
+    output: Option<String>,
+    config: Config,
+}
+
+impl Builder {
+    /// Creates a builder for the SVD (or YAML, JSON) file at `input`
+    pub fn new(input: impl AsRef<Path>) -> Self {
+        let input = input.as_ref().to_path_buf();
+        let config = Config {
+            source_type: SourceType::from_path(&input),
+            ..Default::default()
+        };
+        Self {
+            input,
+            out_dir: None,
+            output: None,
+            config,
+        }
+    }
+
+    /// Target architecture
+    pub fn target(mut self, target: Target) -> Self {
+        self.config.target = target;
+        self
+    }
+
+    /// Target-specific settings YAML file
+    pub fn settings_file(mut self, settings_file: impl AsRef<Path>) -> Self {
+        self.config.settings_file = Some(settings_file.as_ref().to_path_buf());
+        self
+    }
+
+    /// Generate atomic register modification API
+    pub fn atomics(mut self, atomics: bool) -> Self {
+        self.config.atomics = atomics;
+        self
+    }
+
+    /// Feature gating for atomic register modification API
+    pub fn atomics_feature(mut self, feature: impl Into<String>) -> Self {
+        self.config.atomics_feature = Some(feature.into());
+        self
+    }
+
+    /// Implementation of atomic operations
+    pub fn atomics_backend(mut self, atomics_backend: AtomicsBackend) -> Self {
+        self.config.atomics_backend = atomics_backend;
+        self
+    }
+
+    /// Generate single-bit field writers through the Cortex-M bit-band alias region
+    pub fn bitband(mut self, bitband: bool) -> Self {
+        self.config.bitband = bitband;
+        self
+    }
+
+    /// Implementation of register access
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.config.backend = backend;
+        self
+    }
+
+    /// Make advanced checks due to parsing SVD
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.config.strict = strict;
+        self
+    }
+
+    /// Keep lists when generating code of dimElement, instead of trying to generate arrays
+    pub fn keep_list(mut self, keep_list: bool) -> Self {
+        self.config.keep_list = keep_list;
+        self
+    }
+
+    /// Don't add alternateGroup name as prefix to register name
+    pub fn ignore_groups(mut self, ignore_groups: bool) -> Self {
+        self.config.ignore_groups = ignore_groups;
+        self
+    }
+
+    /// Implement `Debug` for readable blocks and registers
+    pub fn impl_debug(mut self, impl_debug: bool) -> Self {
+        self.config.impl_debug = impl_debug;
+        self
+    }
+
+    /// Generate a plain struct of field values for each register
+    pub fn field_structs(mut self, field_structs: bool) -> Self {
+        self.config.field_structs = field_structs;
+        self
+    }
+
+    /// Generate `const fn` setters of fields for register values
+    pub fn const_values(mut self, const_values: bool) -> Self {
+        self.config.const_values = const_values;
+        self
+    }
+
+    /// Feature gating for block and register debug implementation
+    pub fn impl_debug_feature(mut self, feature: impl Into<String>) -> Self {
+        self.config.impl_debug_feature = Some(feature.into());
+        self
+    }
+
+    /// Feature gating for `defmt` implementation of enumerated values
+    pub fn impl_defmt(mut self, feature: impl Into<String>) -> Self {
+        self.config.impl_defmt = Some(feature.into());
+        self
+    }
+
+    /// Set of `ident_format` settings
+    pub fn ident_formats_theme(mut self, theme: IdentFormatsTheme) -> Self {
+        self.config.ident_formats_theme = Some(theme);
+        self
+    }
+
+    /// Change formatting of identifiers of type `name`
+    pub fn ident_format(mut self, name: impl Into<String>, format: IdentFormat) -> Self {
+        self.config.ident_formats.insert(name.into(), format);
+        self
+    }
+
+    /// Add offset to all base addresses on all peripherals
+    pub fn base_address_shift(mut self, shift: u64) -> Self {
+        self.config.base_address_shift = shift;
+        self
+    }
+
+    /// Store the pointer to the register block in peripheral handles
+    pub fn relocatable(mut self, relocatable: bool) -> Self {
+        self.config.relocatable = relocatable;
+        self
+    }
+
+    /// Change any other option of the configuration
+    pub fn config(mut self, f: impl FnOnce(&mut Config)) -> Self {
+        f(&mut self.config);
+        self
+    }
+
+    /// Directory to place generated files. Defaults to `OUT_DIR`
+    pub fn out_dir(mut self, out_dir: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(out_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Name of the generated file. Defaults to the name of the input file with `.rs` extension
+    pub fn output(mut self, name: impl Into<String>) -> Self {
+        self.output = Some(name.into());
+        self
+    }
+
+    /// Generates the code and returns path to the generated file
+    pub fn build(self) -> Result<PathBuf> {
+        let Self {
+            input,
+            out_dir,
+            output,
+            mut config,
+        } = self;
+
+        let out_dir = match out_dir {
+            Some(dir) => dir,
+            None => std::env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| anyhow!("`OUT_DIR` is not set, use `Builder::out_dir`"))?,
+        };
+        let output = match output {
+            Some(output) => output,
+            None => {
+                let stem = input
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .ok_or_else(|| anyhow!("Invalid input file name {}", input.display()))?;
+                format!("{}.rs", stem.to_lowercase())
+            }
+        };
+
+        // `include!` accepts items only
+        config.make_mod = true;
+        config.skip_crate_attributes = true;
+        config.generic_mod = false;
+        config.split = false;
+        config.make_crate = false;
+        config.input = Some(input.clone());
+        config.output_dir = Some(out_dir.clone());
+
+        println!("cargo:rerun-if-changed={}", input.display());
+        let settings = config.settings_file.iter();
+        for path in settings.chain(&config.include).chain(&config.patches) {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+
+        let svd = std::fs::read_to_string(&input)
+            .with_context(|| format!("Cannot read the SVD file {}", input.display()))?;
+        let mut generation = crate::generate(&svd, &config)?;
+
+        let path = out_dir.join(output);
+        let code = generation
+            .files
+            .remove(Path::new("mod.rs"))
+            .ok_or_else(|| anyhow!("Generated code is missing"))?;
+        std::fs::write(&path, code)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+
+        if let Some(device_x) = generation.files.remove(Path::new("device.x")) {
+            std::fs::write(out_dir.join("device.x"), device_x)
+                .context("Could not write device.x")?;
+            if std::env::var_os("CARGO_FEATURE_RT").is_some() {
+                println!("cargo:rustc-link-search={}", out_dir.display());
+            }
+        }
+
+        Ok(path)
+    }
+}
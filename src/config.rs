@@ -1,24 +1,44 @@
 use anyhow::{bail, Result};
 use proc_macro2::Span;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
     str::FromStr,
 };
 use syn::{punctuated::Punctuated, Ident};
 
-use crate::util::path_segment;
+use crate::util::{matches_glob, path_segment};
 
-#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(default)
+)]
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
 #[non_exhaustive]
 pub struct Config {
     pub target: Target,
     pub atomics: bool,
     pub atomics_feature: Option<String>,
+    /// Implementation of atomic operations
+    pub atomics_backend: AtomicsBackend,
+    /// Implementation of register access
+    pub backend: Backend,
+    /// Single-bit field writers through the Cortex-M bit-band alias region
+    pub bitband: bool,
     pub generic_mod: bool,
     pub make_mod: bool,
+    /// Split generated modules into separate files
+    pub split: bool,
+    /// Generate a complete crate: `Cargo.toml`, `build.rs` and sources in `src`
+    pub make_crate: bool,
+    /// Package name of the generated crate
+    pub crate_name: Option<String>,
+    /// Compare generated files with existing ones instead of writing them
+    pub check: bool,
+    /// Record versions, input hashes and configuration in the generated code
+    pub provenance: bool,
     pub skip_crate_attributes: bool,
     pub ignore_groups: bool,
     pub keep_list: bool,
@@ -27,10 +47,18 @@ pub struct Config {
     pub feature_peripheral: bool,
     pub max_cluster_size: bool,
     pub impl_debug: bool,
+    /// Generate a plain struct of field values for each register
+    pub field_structs: bool,
+    /// Generate `const fn` setters of fields for register values
+    pub const_values: bool,
     pub impl_debug_feature: Option<String>,
     pub impl_defmt: Option<String>,
     pub output_dir: Option<PathBuf>,
     pub input: Option<PathBuf>,
+    /// CMSIS-Pack directory or `.pdsc` file to look for the input SVD in
+    pub pack: Option<PathBuf>,
+    /// Name of the device in the CMSIS-Pack
+    pub pack_device: Option<String>,
     pub source_type: SourceType,
     pub log_level: Option<String>,
     pub interrupt_link_section: Option<String>,
@@ -40,6 +68,15 @@ pub struct Config {
     pub ident_formats_theme: Option<IdentFormatsTheme>,
     pub field_names_for_enums: bool,
     pub base_address_shift: u64,
+    /// Peripheral handles carry the pointer to the register block, so they can be created
+    /// for blocks found at runtime
+    pub relocatable: bool,
+    /// Files with additional peripherals merged into the input device
+    pub include: Vec<PathBuf>,
+    /// YAML patch files applied to the device before rendering
+    pub patches: Vec<PathBuf>,
+    /// Elements of the device to generate code for
+    pub filter: Filter,
     /// Path to YAML file with chip-specific settings
     pub settings_file: Option<PathBuf>,
     /// Chip-specific settings
@@ -98,9 +135,75 @@ impl Target {
     }
 }
 
+/// Implementation of atomic operations of the `--atomics` API
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "kebab-case")
+)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub enum AtomicsBackend {
+    /// Atomic types of the `portable-atomic` crate
+    #[default]
+    Portable,
+    /// Atomic types of `core`, available only for widths the target supports
+    Native,
+    /// Read-modify-write in a critical section of the `critical-section` crate
+    CriticalSection,
+    /// Atomic types of `core` for widths the target supports, critical sections for the rest
+    Auto,
+}
+
+/// Byte order of registers
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// Implementation of register access in the `generic` module
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+pub enum Backend {
+    /// Volatile accesses to memory-mapped registers
+    #[default]
+    Vcell,
+    /// Registers simulated in memory of the host for testing drivers
+    Sim,
+    /// Register blocks and registers are raw pointers, no references to registers are made
+    Ptr,
+    /// Registers of devices behind a bus, accessed through a trait implemented by the driver
+    Transport,
+}
+
+impl Backend {
+    /// Register blocks are handles with the address of the block instead of the layout of
+    /// registers in memory
+    pub const fn has_block_handles(self) -> bool {
+        matches!(self, Self::Ptr | Self::Transport)
+    }
+
+    /// Atomic operations with registers are available
+    pub const fn has_atomics(self) -> bool {
+        matches!(self, Self::Vcell | Self::Ptr)
+    }
+}
+
 #[cfg_attr(
     feature = "serde",
-    derive(serde::Deserialize),
+    derive(serde::Deserialize, serde::Serialize),
     serde(rename_all = "lowercase")
 )]
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
@@ -111,6 +214,8 @@ pub enum SourceType {
     Yaml,
     #[cfg(feature = "json")]
     Json,
+    #[cfg(feature = "ipxact")]
+    IpXact,
 }
 
 impl SourceType {
@@ -122,10 +227,21 @@ impl SourceType {
             "yml" | "yaml" => Some(Self::Yaml),
             #[cfg(feature = "json")]
             "json" => Some(Self::Json),
+            #[cfg(feature = "ipxact")]
+            "ipxact" | "xact" => Some(Self::IpXact),
             _ => None,
         }
     }
+    /// Detects [`SourceType`] by file extension. `*.ipxact.xml` files are IP-XACT
     pub fn from_path(path: &Path) -> Self {
+        #[cfg(feature = "ipxact")]
+        if path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.to_lowercase().ends_with(".ipxact.xml"))
+        {
+            return Self::IpXact;
+        }
         path.extension()
             .and_then(|e| e.to_str())
             .and_then(Self::from_extension)
@@ -135,7 +251,7 @@ impl SourceType {
 
 #[cfg_attr(
     feature = "serde",
-    derive(serde::Deserialize),
+    derive(serde::Deserialize, serde::Serialize),
     serde(rename_all = "lowercase")
 )]
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
@@ -168,7 +284,11 @@ pub enum IdentFormatError {
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(default)
+)]
 pub struct IdentFormat {
     // Ident case. `None` means don't change
     pub case: Option<Case>,
@@ -299,6 +419,17 @@ impl IdentFormats {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for IdentFormats {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // sorted for reproducible output
+        serializer.collect_map(self.0.iter().collect::<std::collections::BTreeMap<_, _>>())
+    }
+}
+
 impl Deref for IdentFormats {
     type Target = HashMap<String, IdentFormat>;
     fn deref(&self) -> &Self::Target {
@@ -313,7 +444,7 @@ impl DerefMut for IdentFormats {
 
 #[cfg_attr(
     feature = "serde",
-    derive(serde::Deserialize),
+    derive(serde::Deserialize, serde::Serialize),
     serde(rename_all = "lowercase")
 )]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -321,20 +452,87 @@ pub enum IdentFormatsTheme {
     Legacy,
 }
 
-#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(default)
+)]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+/// Include and exclude lists for elements of the device
+pub struct Filter {
+    pub peripherals: FilterList,
+    pub clusters: FilterList,
+    pub registers: FilterList,
+    pub interrupts: FilterList,
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        [
+            &self.peripherals,
+            &self.clusters,
+            &self.registers,
+            &self.interrupts,
+        ]
+        .iter()
+        .all(|l| l.include.is_empty() && l.exclude.is_empty())
+    }
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(default)
+)]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+/// Patterns of element names.
+///
+/// Patterns are globs (see [`crate::util::matches_glob`]) or regular expressions in slashes
+/// (`/USART[0-9]+/`). A glob containing `.` is matched against the full path of the element
+/// (`PERIPHERAL.CLUSTER.REGISTER`), otherwise against the name. A regular expression must match
+/// the whole name or the whole path.
+pub struct FilterList {
+    /// Keep only matching elements. Empty list keeps everything
+    pub include: Vec<String>,
+    /// Remove matching elements
+    pub exclude: Vec<String>,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(default)
+)]
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
 #[non_exhaustive]
 /// Chip-specific settings
+///
+/// Keys of `peripherals` and `aliases` are peripheral names or globs. Entries of all the keys
+/// matching a peripheral are combined in alphabetical order of keys: a value given by a later key
+/// replaces the one given by an earlier key, so `"*"` can be refined by more specific keys.
 pub struct Settings {
     /// Path to chip HTML generated by svdtools
     pub html_url: Option<url::Url>,
     pub crate_path: Option<CratePath>,
     /// RISC-V specific settings
     pub riscv_config: Option<riscv::RiscvConfig>,
+    /// Base address overrides, byte order and extra instances of peripherals
+    pub peripherals: BTreeMap<String, PeripheralSettings>,
+    /// Hardware set, clear and toggle aliases of registers used by the atomic API
+    pub aliases: BTreeMap<String, AliasSettings>,
+    /// Byte order of registers, replaces `cpu.endian` from SVD
+    pub endian: Option<Endian>,
 }
 
 impl Settings {
     pub fn update_from(&mut self, source: Self) {
+        self.peripherals.extend(source.peripherals);
+        self.aliases.extend(source.aliases);
+        if source.endian.is_some() {
+            self.endian = source.endian;
+        }
         if source.html_url.is_some() {
             self.html_url = source.html_url;
         }
@@ -345,6 +543,94 @@ impl Settings {
             self.riscv_config = source.riscv_config;
         }
     }
+
+    /// Base address, offset and byte order of `peripheral` combined from the matching entries
+    /// of `peripherals`. `instances` are not combined
+    pub fn peripheral_settings(&self, peripheral: &str) -> PeripheralSettings {
+        let mut settings = PeripheralSettings::default();
+        for (pattern, ps) in &self.peripherals {
+            if matches_glob(pattern, peripheral) {
+                settings.base_address = ps.base_address.or(settings.base_address);
+                settings.offset = ps.offset.or(settings.offset);
+                settings.endian = ps.endian.or(settings.endian);
+            }
+        }
+        settings
+    }
+
+    /// Aliases of registers of `peripheral` combined from the matching entries of `aliases`.
+    /// An alias is replaced by a later key that gives its offset or name
+    pub fn peripheral_aliases(&self, peripheral: &str) -> AliasSettings {
+        let mut settings = AliasSettings::default();
+        for (pattern, a) in &self.aliases {
+            if !matches_glob(pattern, peripheral) {
+                continue;
+            }
+            if a.set_offset.is_some() || a.set_name.is_some() {
+                settings.set_offset = a.set_offset;
+                settings.set_name.clone_from(&a.set_name);
+            }
+            if a.clear_offset.is_some() || a.clear_name.is_some() {
+                settings.clear_offset = a.clear_offset;
+                settings.clear_name.clone_from(&a.clear_name);
+            }
+            if a.toggle_offset.is_some() || a.toggle_name.is_some() {
+                settings.toggle_offset = a.toggle_offset;
+                settings.toggle_name.clone_from(&a.toggle_name);
+            }
+        }
+        settings
+    }
+
+    /// Byte order of registers of `peripheral` from `peripherals`, or the byte order of the device
+    pub fn endian(&self, peripheral: &str) -> Option<Endian> {
+        self.peripheral_settings(peripheral).endian.or(self.endian)
+    }
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(default)
+)]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+/// Placement of a peripheral
+pub struct PeripheralSettings {
+    /// Absolute base address, replaces the one from SVD
+    pub base_address: Option<u64>,
+    /// Offset added to the base address from SVD, unused when `base_address` is given
+    pub offset: Option<i64>,
+    /// Extra instances of the peripheral register block: name and base address
+    pub instances: BTreeMap<String, u64>,
+    /// Byte order of registers of the peripheral, e.g. behind a byte-swapping bus bridge
+    pub endian: Option<Endian>,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(default)
+)]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[non_exhaustive]
+/// Registers that set, clear or toggle the bits written to them in another register.
+///
+/// An alias is a window at a fixed offset from the register (`offset`) or a sibling register
+/// in the same block (`name`, where `{}` is replaced with the name of the register, e.g. `{}SET`).
+pub struct AliasSettings {
+    /// Offset of the set alias in bytes
+    pub set_offset: Option<u64>,
+    /// Offset of the clear alias in bytes
+    pub clear_offset: Option<u64>,
+    /// Offset of the toggle alias in bytes
+    pub toggle_offset: Option<u64>,
+    /// Name of the set alias register
+    pub set_name: Option<String>,
+    /// Name of the clear alias register
+    pub clear_name: Option<String>,
+    /// Name of the toggle alias register
+    pub toggle_name: Option<String>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -372,6 +658,17 @@ impl<'de> serde::Deserialize<'de> for CratePath {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for CratePath {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use quote::ToTokens;
+        serializer.collect_str(&self.0.to_token_stream())
+    }
+}
+
 impl FromStr for CratePath {
     type Err = syn::Error;
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -0,0 +1,362 @@
+//! Locating errors in the input SVD
+//!
+//! Errors of peripherals, clusters, registers and fields are wrapped in [`SvdError::Element`]
+//! with the path of the element. [`locate`] finds the innermost element of the error chain
+//! in the input XML and turns the error into [`SvdError::Located`], which is displayed
+//! compiler-style with the line of the source and a caret under the name of the element.
+//!
+//! Locating elements requires the `diagnostics` feature, without it [`locate`] returns
+//! the error unchanged.
+
+#[cfg(feature = "diagnostics")]
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use anyhow::Result;
+#[cfg(feature = "diagnostics")]
+use roxmltree::{Document, Node};
+
+use crate::config::Config;
+#[cfg(feature = "diagnostics")]
+use crate::config::SourceType;
+use crate::svd::{self, RegisterCluster, ValidateLevel};
+use crate::SvdError;
+
+/// Kind of SVD element
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElementKind {
+    Peripheral,
+    Cluster,
+    Register,
+    Field,
+}
+
+impl Display for ElementKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Peripheral => "peripheral",
+            Self::Cluster => "cluster",
+            Self::Register => "register",
+            Self::Field => "field",
+        })
+    }
+}
+
+/// Attaches the SVD element to errors
+pub trait ElementContext<T> {
+    /// Wraps the error in [`SvdError::Element`] with the path of the element
+    /// (`PERIPHERAL.CLUSTER.REGISTER.FIELD`) and the message
+    fn element_context(
+        self,
+        kind: ElementKind,
+        path: impl Display,
+        message: impl FnOnce() -> String,
+    ) -> Result<T>;
+}
+
+impl<T, E: Into<anyhow::Error>> ElementContext<T> for Result<T, E> {
+    fn element_context(
+        self,
+        kind: ElementKind,
+        path: impl Display,
+        message: impl FnOnce() -> String,
+    ) -> Result<T> {
+        self.map_err(|source| {
+            SvdError::Element {
+                kind,
+                path: path.to_string(),
+                message: message(),
+                source: source.into(),
+            }
+            .into()
+        })
+    }
+}
+
+/// Error position in the input file
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    pub kind: ElementKind,
+    /// Path of the element
+    pub path: String,
+    /// Root cause of the error
+    pub message: String,
+    /// Name of the input file
+    pub file: String,
+    /// Line, starting from 1
+    pub line: u32,
+    /// Column, starting from 1
+    pub column: u32,
+    source_line: String,
+    len: usize,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = self.line.to_string();
+        let gutter = " ".repeat(line.len());
+        // keep tabs, so the caret is under the name
+        let indent: String = self
+            .source_line
+            .chars()
+            .take(self.column as usize - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        writeln!(f, "{}", self.message)?;
+        writeln!(f, "{gutter}--> {}:{line}:{}", self.file, self.column)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line} | {}", self.source_line)?;
+        writeln!(f, "{gutter} | {indent}{}", "^".repeat(self.len.max(1)))?;
+        write!(f, "{gutter} = note: in {} `{}`", self.kind, self.path)
+    }
+}
+
+/// Positions of element names in the input XML
+#[cfg(feature = "diagnostics")]
+struct SourceMap<'a> {
+    input: &'a str,
+    names: HashMap<String, (u32, u32, usize)>,
+}
+
+#[cfg(feature = "diagnostics")]
+impl<'a> SourceMap<'a> {
+    fn new(input: &'a str) -> Option<Self> {
+        let doc = Document::parse(input).ok()?;
+        let mut map = Self {
+            input,
+            names: HashMap::new(),
+        };
+        for p in children(doc.root_element(), "peripherals").flat_map(|n| children(n, "peripheral"))
+        {
+            if let Some(name) = map.add(&doc, p, "") {
+                map.add_registers(&doc, p, &name);
+            }
+        }
+        Some(map)
+    }
+
+    /// Adds the element, returns its path
+    fn add(&mut self, doc: &Document, node: Node, parent: &str) -> Option<String> {
+        let name = children(node, "name").next()?;
+        let text = name.first_child().filter(|n| n.is_text())?;
+        let pos = doc.text_pos_at(text.range().start);
+        let value = text.text()?;
+        let trimmed = value.trim_start();
+        let offset = value[..value.len() - trimmed.len()].chars().count() as u32;
+        let path = if parent.is_empty() {
+            trimmed.trim_end().to_string()
+        } else {
+            format!("{parent}.{}", trimmed.trim_end())
+        };
+        self.names.insert(
+            path.clone(),
+            (
+                pos.row,
+                pos.col + offset,
+                trimmed.trim_end().chars().count(),
+            ),
+        );
+        Some(path)
+    }
+
+    fn add_registers(&mut self, doc: &Document, node: Node, parent: &str) {
+        for rc in children(node, "registers")
+            .chain(Some(node).filter(|n| n.has_tag_name("cluster")))
+            .flat_map(|n| n.children())
+        {
+            match rc.tag_name().name() {
+                "register" => {
+                    if let Some(path) = self.add(doc, rc, parent) {
+                        for f in children(rc, "fields").flat_map(|n| children(n, "field")) {
+                            self.add(doc, f, &path);
+                        }
+                    }
+                }
+                "cluster" => {
+                    if let Some(path) = self.add(doc, rc, parent) {
+                        self.add_registers(doc, rc, &path);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Finds the element or its nearest parent present in the file
+    fn find(
+        &self,
+        kind: ElementKind,
+        path: &str,
+        message: String,
+        file: String,
+    ) -> Option<Diagnostic> {
+        let mut key = path;
+        loop {
+            if let Some(&(line, column, len)) = self.names.get(key) {
+                return Some(Diagnostic {
+                    kind,
+                    path: path.into(),
+                    message,
+                    file,
+                    line,
+                    column,
+                    source_line: self
+                        .input
+                        .lines()
+                        .nth(line as usize - 1)
+                        .unwrap_or_default()
+                        .into(),
+                    len,
+                });
+            }
+            key = &key[..key.rfind('.')?];
+        }
+    }
+}
+
+#[cfg(feature = "diagnostics")]
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |n| n.has_tag_name(name))
+}
+
+/// Adds the position of the innermost element of the error chain in the input file.
+///
+/// Returns the error unchanged if the input is not XML or the element is not found.
+#[cfg(feature = "diagnostics")]
+pub fn locate(err: anyhow::Error, input: &str, config: &Config) -> anyhow::Error {
+    if config.source_type != SourceType::Xml {
+        return err;
+    }
+    let Some((kind, path)) = err
+        .chain()
+        .filter_map(|e| match e.downcast_ref::<SvdError>() {
+            Some(SvdError::Element { kind, path, .. }) => Some((*kind, path.clone())),
+            _ => None,
+        })
+        .last()
+    else {
+        return err;
+    };
+    let Some(map) = SourceMap::new(input) else {
+        return err;
+    };
+    let file = config
+        .input
+        .as_ref()
+        .map_or_else(|| "<stdin>".into(), |p| p.display().to_string());
+    match map.find(kind, &path, err.root_cause().to_string(), file) {
+        Some(diagnostic) => SvdError::Located {
+            diagnostic: Box::new(diagnostic),
+            source: err,
+        }
+        .into(),
+        None => err,
+    }
+}
+
+/// Adds the position of the innermost element of the error chain in the input file.
+///
+/// Always returns the error unchanged, as svd2rust was compiled without the `diagnostics` feature.
+#[cfg(not(feature = "diagnostics"))]
+pub fn locate(err: anyhow::Error, _input: &str, _config: &Config) -> anyhow::Error {
+    err
+}
+
+/// Validates the device. Errors are attributed to the innermost invalid element
+pub fn validate(device: &svd::Device, lvl: ValidateLevel) -> Result<()> {
+    let Err(err) = device.validate_all(lvl) else {
+        return Ok(());
+    };
+    for p in &device.peripherals {
+        if let Some((kind, path, err)) =
+            invalid_registers(p.registers.as_deref().unwrap_or(&[]), &p.name, lvl).or_else(|| {
+                p.validate(lvl)
+                    .err()
+                    .map(|e| (ElementKind::Peripheral, p.name.clone(), e))
+            })
+        {
+            return Err::<(), _>(err)
+                .element_context(kind, &path, || format!("invalid {kind} '{path}'"));
+        }
+    }
+    Err(err.into())
+}
+
+fn invalid_registers(
+    registers: &[RegisterCluster],
+    parent: &str,
+    lvl: ValidateLevel,
+) -> Option<(ElementKind, String, svd::SvdError)> {
+    for rc in registers {
+        match rc {
+            RegisterCluster::Register(r) => {
+                let path = format!("{parent}.{}", r.name);
+                for f in r.fields.as_deref().unwrap_or(&[]) {
+                    let err = f
+                        .validate(lvl)
+                        .and_then(|_| f.enumerated_values.iter().try_for_each(|e| e.validate(lvl)));
+                    if let Err(e) = err {
+                        return Some((ElementKind::Field, format!("{path}.{}", f.name), e));
+                    }
+                }
+                if let Err(e) = r.validate(lvl) {
+                    return Some((ElementKind::Register, path, e));
+                }
+            }
+            RegisterCluster::Cluster(c) => {
+                let path = format!("{parent}.{}", c.name);
+                if let Some(invalid) = invalid_registers(&c.children, &path, lvl) {
+                    return Some(invalid);
+                }
+                if let Err(e) = c.validate(lvl) {
+                    return Some((ElementKind::Cluster, path, e));
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(feature = "diagnostics")]
+#[test]
+fn locate_register() {
+    let input = "<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>UART</name>
+      <registers>
+        <register>
+\t<name> CR </name>
+        </register>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>";
+    let err = Err::<(), _>(anyhow::anyhow!("no size"))
+        .element_context(ElementKind::Register, "UART.CR", || {
+            "can't render register 'CR'".into()
+        })
+        .element_context(ElementKind::Peripheral, "UART", || {
+            "can't render peripheral 'UART'".into()
+        })
+        .unwrap_err();
+    let config = Config {
+        input: Some("test.svd".into()),
+        ..Default::default()
+    };
+    let err = locate(err, input, &config);
+    assert_eq!(
+        err.to_string(),
+        "no size
+ --> test.svd:8:9
+  |
+8 | \t<name> CR </name>
+  | \t       ^^
+  = note: in register `UART.CR`"
+    );
+}
@@ -0,0 +1,405 @@
+//! Pruning of the device with include and exclude lists
+//!
+//! Elements needed by kept elements through `derivedFrom` (of the element itself, of its fields
+//! or of their enumerated values) are kept even if they are filtered out. A bare name of
+//! enumerated values refers to the ones of the same register or of another register of the same
+//! peripheral.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::config::{Filter, FilterList};
+use crate::svd::{Device, RegisterCluster};
+use crate::util::matches_glob;
+
+enum Pattern {
+    Glob(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn new(pattern: &str) -> Result<Self> {
+        Ok(
+            match pattern.strip_prefix('/').and_then(|p| p.strip_suffix('/')) {
+                Some(re) => Self::Regex(
+                    Regex::new(&format!("^(?:{re})$"))
+                        .with_context(|| format!("Invalid regular expression `{pattern}`"))?,
+                ),
+                None => Self::Glob(pattern.into()),
+            },
+        )
+    }
+
+    fn matches(&self, name: &str, path: &str) -> bool {
+        match self {
+            Self::Glob(glob) if glob.contains('.') => matches_glob(glob, path),
+            Self::Glob(glob) => matches_glob(glob, name),
+            Self::Regex(re) => re.is_match(name) || re.is_match(path),
+        }
+    }
+}
+
+struct Patterns {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl Patterns {
+    fn new(list: &FilterList) -> Result<Self> {
+        let compile = |patterns: &[String]| -> Result<Vec<Pattern>> {
+            patterns.iter().map(|p| Pattern::new(p)).collect()
+        };
+        Ok(Self {
+            include: compile(&list.include)?,
+            exclude: compile(&list.exclude)?,
+        })
+    }
+
+    fn keeps(&self, name: &str, path: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches(name, path)))
+            && !self.exclude.iter().any(|p| p.matches(name, path))
+    }
+}
+
+/// Element of the device collected before filtering
+struct Element {
+    /// Kept by the filter itself
+    selected: bool,
+    children: Vec<String>,
+    /// `derivedFrom` references as written in the file
+    derives: Vec<String>,
+    /// `derivedFrom` references of enumerated values of fields
+    enum_derives: Vec<String>,
+    /// Names of enumerated values of fields
+    enums: Vec<String>,
+}
+
+/// Removes peripherals, clusters, registers and interrupts rejected by the filter
+pub fn apply(device: &mut Device, filter: &Filter) -> Result<()> {
+    let peripherals = Patterns::new(&filter.peripherals).context("peripherals filter")?;
+    let clusters = Patterns::new(&filter.clusters).context("clusters filter")?;
+    let registers = Patterns::new(&filter.registers).context("registers filter")?;
+    let interrupts = Patterns::new(&filter.interrupts).context("interrupts filter")?;
+
+    let mut elements = HashMap::new();
+    for p in &device.peripherals {
+        let children = collect(
+            p.registers.as_deref().unwrap_or(&[]),
+            &p.name,
+            &clusters,
+            &registers,
+            &mut elements,
+        );
+        elements.insert(
+            p.name.clone(),
+            Element {
+                selected: peripherals.keeps(&p.name, &p.name),
+                children,
+                derives: p.derived_from.iter().cloned().collect(),
+                enum_derives: Vec::new(),
+                enums: Vec::new(),
+            },
+        );
+    }
+
+    let mut kept = HashSet::new();
+    for p in &device.peripherals {
+        if elements[&p.name].selected {
+            keep(&p.name, &elements, &mut kept);
+        }
+    }
+    // add dependencies until nothing changes
+    loop {
+        let mut needed = Vec::new();
+        for path in &kept {
+            let element = &elements[path];
+            let deps = element
+                .derives
+                .iter()
+                .map(|derive| resolve(derive, path, &elements))
+                .chain(
+                    element
+                        .enum_derives
+                        .iter()
+                        .map(|derive| resolve_enum(derive, path, &elements)),
+                );
+            for dep in deps.flatten() {
+                if !kept.contains(&dep) {
+                    needed.push(dep);
+                }
+            }
+        }
+        if needed.is_empty() {
+            break;
+        }
+        for dep in needed {
+            keep(&dep, &elements, &mut kept);
+            // parents of the dependency, with their selected children like any kept element
+            let mut parent = dep.as_str();
+            while let Some(i) = parent.rfind('.') {
+                parent = &parent[..i];
+                keep(parent, &elements, &mut kept);
+            }
+        }
+    }
+
+    device.peripherals.retain(|p| kept.contains(&p.name));
+    for p in device.peripherals.iter_mut() {
+        let name = p.name.clone();
+        if let Some(regs) = p.registers.as_mut() {
+            prune(regs, &name, &kept);
+        }
+        p.interrupt.retain(|i| interrupts.keeps(&i.name, &i.name));
+    }
+    Ok(())
+}
+
+/// Collects registers and clusters, returns paths of the children selected by the filters
+fn collect(
+    ercs: &[RegisterCluster],
+    parent: &str,
+    clusters: &Patterns,
+    registers: &Patterns,
+    elements: &mut HashMap<String, Element>,
+) -> Vec<String> {
+    let mut paths = Vec::new();
+    for erc in ercs {
+        match erc {
+            RegisterCluster::Register(r) => {
+                let path = format!("{parent}.{}", r.name);
+                let mut derives: Vec<String> = r.derived_from.iter().cloned().collect();
+                let mut enum_derives = Vec::new();
+                let mut enums = Vec::new();
+                for f in r.fields.as_deref().unwrap_or(&[]) {
+                    derives.extend(f.derived_from.iter().cloned());
+                    for e in &f.enumerated_values {
+                        enum_derives.extend(e.derived_from.iter().cloned());
+                        enums.extend(e.name.iter().cloned());
+                    }
+                }
+                let selected = registers.keeps(&r.name, &path);
+                if selected {
+                    paths.push(path.clone());
+                }
+                elements.insert(
+                    path,
+                    Element {
+                        selected,
+                        children: Vec::new(),
+                        derives,
+                        enum_derives,
+                        enums,
+                    },
+                );
+            }
+            RegisterCluster::Cluster(c) => {
+                let path = format!("{parent}.{}", c.name);
+                let children = collect(&c.children, &path, clusters, registers, elements);
+                let selected = clusters.keeps(&c.name, &path);
+                if selected {
+                    paths.push(path.clone());
+                }
+                elements.insert(
+                    path,
+                    Element {
+                        selected,
+                        children,
+                        derives: c.derived_from.iter().cloned().collect(),
+                        enum_derives: Vec::new(),
+                        enums: Vec::new(),
+                    },
+                );
+            }
+        }
+    }
+    paths
+}
+
+/// Keeps the element and its selected children
+fn keep(path: &str, elements: &HashMap<String, Element>, kept: &mut HashSet<String>) {
+    if kept.insert(path.to_string()) {
+        for child in &elements[path].children {
+            keep(child, elements, kept);
+        }
+    }
+}
+
+/// Finds the element `derive` refers to from the element at `path`.
+///
+/// References are absolute or relative to one of the parents. References to fields and
+/// enumerated values resolve to the enclosing register.
+fn resolve(derive: &str, path: &str, elements: &HashMap<String, Element>) -> Option<String> {
+    let mut scopes = Vec::new();
+    let mut scope = path;
+    while let Some(i) = scope.rfind('.') {
+        scope = &scope[..i];
+        scopes.push(format!("{scope}."));
+    }
+    scopes.push(String::new());
+    for scope in scopes {
+        let mut candidate = format!("{scope}{derive}");
+        loop {
+            if elements.contains_key(&candidate) {
+                return Some(candidate);
+            }
+            match candidate.rfind('.') {
+                Some(i) if i >= scope.len() => candidate.truncate(i),
+                _ => break,
+            }
+        }
+    }
+    None
+}
+
+/// Finds the register with the enumerated values `derive` refers to from the register at `path`.
+///
+/// A bare name refers to enumerated values of the same register or, failing that, of another
+/// register of the same peripheral. Other references are resolved like elements.
+fn resolve_enum(derive: &str, path: &str, elements: &HashMap<String, Element>) -> Option<String> {
+    if derive.contains('.') {
+        return resolve(derive, path, elements);
+    }
+    if elements[path].enums.iter().any(|name| name == derive) {
+        return Some(path.into());
+    }
+    let peripheral = path.split('.').next().unwrap_or(path);
+    let prefix = format!("{peripheral}.");
+    elements
+        .iter()
+        .filter(|(p, e)| p.starts_with(&prefix) && e.enums.iter().any(|name| name == derive))
+        .map(|(p, _)| p)
+        .min()
+        .cloned()
+}
+
+fn prune(ercs: &mut Vec<RegisterCluster>, parent: &str, kept: &HashSet<String>) {
+    ercs.retain_mut(|erc| {
+        let name = match erc {
+            RegisterCluster::Register(r) => &r.name,
+            RegisterCluster::Cluster(c) => &c.name,
+        };
+        let path = format!("{parent}.{name}");
+        if !kept.contains(&path) {
+            return false;
+        }
+        if let RegisterCluster::Cluster(c) = erc {
+            prune(&mut c.children, &path, kept);
+        }
+        true
+    });
+}
+
+#[test]
+fn filter_derived() {
+    let svd = r#"
+<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>USART1</name>
+      <baseAddress>0x40000000</baseAddress>
+      <interrupt><name>USART1</name><value>1</value></interrupt>
+      <registers>
+        <register><name>CR</name><addressOffset>0x0</addressOffset><size>32</size></register>
+        <register><name>DBG</name><addressOffset>0x4</addressOffset><size>32</size></register>
+      </registers>
+    </peripheral>
+    <peripheral derivedFrom="USART1">
+      <name>USART2</name>
+      <baseAddress>0x40001000</baseAddress>
+      <interrupt><name>USART2</name><value>2</value></interrupt>
+    </peripheral>
+    <peripheral>
+      <name>TIM1</name>
+      <baseAddress>0x40002000</baseAddress>
+      <registers>
+        <register derivedFrom="USART1.DBG"><name>CNT</name><addressOffset>0x0</addressOffset></register>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>"#;
+    let mut device = svd_parser::parse(svd).unwrap();
+    let filter = Filter {
+        peripherals: FilterList {
+            include: vec!["USART2".into(), "/TIM[0-9]+/".into()],
+            exclude: Vec::new(),
+        },
+        registers: FilterList {
+            include: Vec::new(),
+            exclude: vec!["*.DBG".into()],
+        },
+        interrupts: FilterList {
+            include: Vec::new(),
+            exclude: vec!["USART?".into()],
+        },
+        ..Default::default()
+    };
+    apply(&mut device, &filter).unwrap();
+    let names: Vec<_> = device.peripherals.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["USART1", "USART2", "TIM1"]);
+    // `DBG` is needed by `TIM1.CNT`
+    assert_eq!(device.peripherals[0].registers.as_ref().unwrap().len(), 2);
+    assert!(device.peripherals.iter().all(|p| p.interrupt.is_empty()));
+}
+
+#[test]
+fn filter_enum_derived() {
+    let svd = r#"
+<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>UART</name>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <register>
+          <name>CR1</name><addressOffset>0x0</addressOffset><size>32</size>
+          <fields>
+            <field>
+              <name>MODE</name><bitOffset>0</bitOffset><bitWidth>1</bitWidth>
+              <enumeratedValues>
+                <name>MODES</name>
+                <enumeratedValue><name>A</name><value>0</value></enumeratedValue>
+                <enumeratedValue><name>B</name><value>1</value></enumeratedValue>
+              </enumeratedValues>
+            </field>
+          </fields>
+        </register>
+        <register>
+          <name>CR2</name><addressOffset>0x4</addressOffset><size>32</size>
+          <fields>
+            <field>
+              <name>MODE</name><bitOffset>0</bitOffset><bitWidth>1</bitWidth>
+              <enumeratedValues derivedFrom="MODES"/>
+            </field>
+          </fields>
+        </register>
+        <register><name>SR</name><addressOffset>0x8</addressOffset><size>32</size></register>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>"#;
+    let mut device = svd_parser::parse(svd).unwrap();
+    let filter = Filter {
+        registers: FilterList {
+            include: vec!["CR2".into()],
+            exclude: Vec::new(),
+        },
+        ..Default::default()
+    };
+    apply(&mut device, &filter).unwrap();
+    // `MODES` of `CR2.MODE` are defined in `CR1`
+    let names: Vec<_> = device.peripherals[0]
+        .registers
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(|r| match r {
+            RegisterCluster::Register(r) => r.name.as_str(),
+            RegisterCluster::Cluster(c) => c.name.as_str(),
+        })
+        .collect();
+    assert_eq!(names, ["CR1", "CR2"]);
+}
@@ -1,21 +1,32 @@
-use crate::svd::{array::names, Device, Peripheral};
-use proc_macro2::{Span, TokenStream};
+use crate::svd::{self, array::names, Device, Peripheral};
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 
 use log::{debug, warn};
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
 
-use crate::config::{Config, Target};
+use crate::config::{AtomicsBackend, Backend, Config, Endian, Target};
+use crate::diagnostic::{ElementContext, ElementKind};
 use crate::util::{self, ident};
-use anyhow::{Context, Result};
+use anyhow::{bail, Result};
 
 use crate::generate::{interrupt, peripheral, riscv};
 
 /// Whole device generation
 pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<TokenStream> {
     let index = svd_parser::expand::Index::create(d);
+
+    // big-endian registers from SVD, unless settings give the byte order. Registers of
+    // little-endian devices keep the default byte order of the target
+    let endian_config;
+    let config = match d.cpu.as_ref().map(|cpu| cpu.endian) {
+        Some(svd::Endian::Big) if config.settings.endian.is_none() => {
+            let mut c = config.clone();
+            c.settings.endian = Some(Endian::Big);
+            endian_config = c;
+            &endian_config
+        }
+        _ => config,
+    };
     let mut out = TokenStream::new();
 
     let commit_info = {
@@ -62,10 +73,10 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
         });
     }
 
-    out.extend(quote! {
-        use core::ops::Deref;
-        use core::marker::PhantomData;
-    });
+    out.extend(quote! { use core::ops::Deref; });
+    if !config.relocatable && !config.backend.has_block_handles() {
+        out.extend(quote! { use core::marker::PhantomData; });
+    }
 
     // Retaining the previous assumption
     let mut fpu_present = true;
@@ -81,6 +92,14 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
         fpu_present = cpu.fpu_present;
     }
 
+    if config.backend == Backend::Transport {
+        let bits = util::unsuffixed(u64::from(d.address_unit_bits));
+        out.extend(quote! {
+            ///Number of data bits uniquely selected by each address
+            pub const ADDRESS_UNIT_BITS: u32 = #bits;
+        });
+    }
+
     let core_peripherals: &[_] = if fpu_present {
         &[
             "CBP", "CPUID", "DCB", "DWT", "FPB", "FPU", "ITM", "MPU", "NVIC", "SCB", "SYST", "TPIU",
@@ -140,26 +159,7 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
         _ => {}
     }
 
-    let generic_file = include_str!("generic.rs");
-    let generic_reg_file = include_str!("generic_reg_vcell.rs");
-    let generic_atomic_file = include_str!("generic_atomic.rs");
     if config.generic_mod {
-        let mut file = File::create(
-            config
-                .output_dir
-                .as_deref()
-                .unwrap_or(Path::new("."))
-                .join("generic.rs"),
-        )?;
-        writeln!(file, "{generic_file}")?;
-        writeln!(file, "{generic_reg_file}")?;
-        if config.atomics {
-            if let Some(atomics_feature) = config.atomics_feature.as_ref() {
-                writeln!(file, "#[cfg(feature = \"{atomics_feature}\")]")?;
-            }
-            writeln!(file, "\n{generic_atomic_file}")?;
-        }
-
         if !config.make_mod {
             out.extend(quote! {
                 #[allow(unused_imports)]
@@ -169,14 +169,7 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
             });
         }
     } else {
-        let mut tokens = syn::parse_file(generic_file)?.into_token_stream();
-        syn::parse_file(generic_reg_file)?.to_tokens(&mut tokens);
-        if config.atomics {
-            if let Some(atomics_feature) = config.atomics_feature.as_ref() {
-                quote!(#[cfg(feature = #atomics_feature)]).to_tokens(&mut tokens);
-            }
-            syn::parse_file(generic_atomic_file)?.to_tokens(&mut tokens);
-        }
+        let tokens = render_generic(config)?;
 
         out.extend(quote! {
             #[allow(unused_imports)]
@@ -229,15 +222,19 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
         }
 
         debug!("Rendering peripheral {}", p.name);
-        let periph = peripheral::render(p, &index, config).with_context(|| {
-            let group_name = p.group_name.as_deref().unwrap_or("No group name");
-            let mut context_string =
-                format!("can't render peripheral '{}', group '{group_name}'", p.name);
-            if let Some(dname) = p.derived_from.as_ref() {
-                context_string += &format!(", derived from: '{dname}'");
-            }
-            context_string
-        })?;
+        let periph = peripheral::render(p, &index, config).element_context(
+            ElementKind::Peripheral,
+            &p.name,
+            || {
+                let group_name = p.group_name.as_deref().unwrap_or("No group name");
+                let mut context_string =
+                    format!("can't render peripheral '{}', group '{group_name}'", p.name);
+                if let Some(dname) = p.derived_from.as_ref() {
+                    context_string += &format!(", derived from: '{dname}'");
+                }
+                context_string
+            },
+        )?;
 
         out.extend(periph);
 
@@ -343,3 +340,99 @@ pub fn render(d: &Device, config: &Config, device_x: &mut String) -> Result<Toke
 
     Ok(out)
 }
+
+/// Content of the `generic` module
+pub fn render_generic(config: &Config) -> Result<TokenStream> {
+    let generic_file = include_str!("generic.rs");
+    let generic_reg_file = match config.backend {
+        Backend::Vcell => include_str!("generic_reg_vcell.rs"),
+        Backend::Sim => include_str!("generic_reg_sim.rs"),
+        Backend::Ptr => include_str!("generic_reg_ptr.rs"),
+        Backend::Transport => include_str!("generic_reg_transport.rs"),
+    };
+    let generic_atomic_file = include_str!("generic_atomic.rs");
+    let generic_bitband_file = include_str!("generic_bitband.rs");
+
+    let mut tokens = syn::parse_file(generic_file)?.into_token_stream();
+    syn::parse_file(generic_reg_file)?.to_tokens(&mut tokens);
+    if config.atomics {
+        if !config.backend.has_atomics() {
+            bail!("Atomic operations are not available with `sim` and `transport` backends");
+        }
+        if let Some(atomics_feature) = config.atomics_feature.as_ref() {
+            quote!(#[cfg(feature = #atomics_feature)]).to_tokens(&mut tokens);
+        }
+        let atomic = syn::parse_file(generic_atomic_file)?;
+        let impls = atomic_impls(config.atomics_backend);
+        tokens.extend(quote! {
+            mod atomic {
+                #atomic
+                #impls
+            }
+        });
+    }
+    if config.bitband {
+        if config.target != Target::CortexM || !config.backend.has_atomics() {
+            bail!(
+                "Bit-band alias writers are available only for `cortex-m` target with memory-mapped registers"
+            );
+        }
+        syn::parse_file(generic_bitband_file)?.to_tokens(&mut tokens);
+    }
+    Ok(tokens)
+}
+
+/// Implementations of `AtomicOperations` for register widths
+fn atomic_impls(backend: AtomicsBackend) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    for (ty, atomic_ty, bits) in [
+        ("u8", "AtomicU8", "8"),
+        ("u16", "AtomicU16", "16"),
+        ("u32", "AtomicU32", "32"),
+        ("u64", "AtomicU64", "64"),
+    ] {
+        let ty = Ident::new(ty, Span::call_site());
+        let atomic_ty = Ident::new(atomic_ty, Span::call_site());
+        tokens.extend(match backend {
+            AtomicsBackend::Portable => {
+                let cfg = match bits {
+                    // Exclude 16-bit archs from 32-bit atomics
+                    "32" => Some(quote!(not(target_pointer_width = "16"))),
+                    // Enable 64-bit atomics for 64-bit RISCV
+                    "64" => Some(quote!(any(
+                        target_pointer_width = "64",
+                        target_has_atomic = "64"
+                    ))),
+                    _ => None,
+                };
+                match cfg {
+                    Some(cfg) => quote! {
+                        #[cfg(#cfg)]
+                        impl_atomics!(#ty, portable_atomic::#atomic_ty);
+                        #[cfg(not(#cfg))]
+                        impl_unsupported!(#ty);
+                    },
+                    None => quote! {
+                        impl_atomics!(#ty, portable_atomic::#atomic_ty);
+                    },
+                }
+            }
+            AtomicsBackend::Native => quote! {
+                #[cfg(target_has_atomic = #bits)]
+                impl_atomics!(#ty, core::sync::atomic::#atomic_ty);
+                #[cfg(not(target_has_atomic = #bits))]
+                impl_unsupported!(#ty);
+            },
+            AtomicsBackend::CriticalSection => quote! {
+                impl_critical_section!(#ty);
+            },
+            AtomicsBackend::Auto => quote! {
+                #[cfg(target_has_atomic = #bits)]
+                impl_atomics!(#ty, core::sync::atomic::#atomic_ty);
+                #[cfg(not(target_has_atomic = #bits))]
+                impl_critical_section!(#ty);
+            },
+        });
+    }
+    tokens
+}
@@ -16,6 +16,8 @@ pub trait RawReg:
     fn mask<const WI: u8>() -> Self;
     /// Mask for bits of width 1
     fn one() -> Self;
+    /// Converts the value between native byte order and byte order `endian`
+    fn swap_endian(self, endian: Endian) -> Self;
 }
 
 macro_rules! raw_reg {
@@ -29,6 +31,14 @@ macro_rules! raw_reg {
             fn one() -> Self {
                 1
             }
+            #[inline(always)]
+            fn swap_endian(self, endian: Endian) -> Self {
+                match endian {
+                    Endian::Native => self,
+                    Endian::Little => <$U>::from_le(self),
+                    Endian::Big => <$U>::from_be(self),
+                }
+            }
         }
         const fn $mask<const WI: u8>() -> $U {
             <$U>::MAX >> ($size - WI)
@@ -44,10 +54,24 @@ raw_reg!(u16, 16, mask_u16);
 raw_reg!(u32, 32, mask_u32);
 raw_reg!(u64, 64, mask_u64);
 
+/// Byte order of a register
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endian {
+    /// Byte order of the target
+    Native,
+    /// Little-endian
+    Little,
+    /// Big-endian
+    Big,
+}
+
 /// Raw register type
 pub trait RegisterSpec {
     /// Raw register type (`u8`, `u16`, `u32`, ...).
     type Ux: RawReg;
+
+    /// Byte order of the register. Bytes are swapped on access if it differs from the target.
+    const ENDIAN: Endian = Endian::Native;
 }
 
 /// Raw field type
@@ -78,6 +102,15 @@ pub trait Writable: RegisterSpec {
 
     /// Specifies the register bits that are not changed if you pass `0` and are changed if you pass `1`
     const ONE_TO_MODIFY_FIELDS_BITMAP: Self::Ux;
+
+    /// Offset in bytes of the hardware alias which sets the bits written to it
+    const SET_ALIAS: Option<isize> = None;
+
+    /// Offset in bytes of the hardware alias which clears the bits written to it
+    const CLEAR_ALIAS: Option<isize> = None;
+
+    /// Offset in bytes of the hardware alias which toggles the bits written to it
+    const TOGGLE_ALIAS: Option<isize> = None;
 }
 
 /// Reset value of the register.
@@ -95,6 +128,35 @@ pub trait Resettable: RegisterSpec {
     }
 }
 
+/// Plain `Copy` struct with a member for each field of the register.
+pub trait FieldValues: RegisterSpec + Sized {
+    /// Values of fields of the register
+    type Fields: Copy;
+}
+
+/// Values of fields can be read from the register.
+///
+/// This enables the `R::fields` and `read_value` methods.
+pub trait ReadFields: Readable + FieldValues {
+    /// Values of fields of the register value
+    fn unpack(r: &R<Self>) -> Self::Fields;
+}
+
+/// Values of fields can be written to the register.
+///
+/// This enables the `W::set_fields` and `write_value` methods.
+pub trait WriteFields: Writable + FieldValues {
+    /// Is it safe to write any values of fields
+    type FieldsSafety;
+
+    /// Sets writable fields of the writer to values of `fields`
+    ///
+    /// # Safety
+    ///
+    /// Raw values of fields may be invalid for the register
+    unsafe fn pack(fields: Self::Fields, w: &mut W<Self>) -> &mut W<Self>;
+}
+
 #[doc(hidden)]
 pub mod raw {
     use super::{marker, BitM, FieldSpec, RegisterSpec, Unsafe, Writable};
@@ -218,6 +280,14 @@ impl<REG: RegisterSpec> R<REG> {
     }
 }
 
+impl<REG: ReadFields> R<REG> {
+    /// Reads values of all fields.
+    #[inline(always)]
+    pub fn fields(&self) -> REG::Fields {
+        REG::unpack(self)
+    }
+}
+
 impl<REG: RegisterSpec, FI> PartialEq<FI> for R<REG>
 where
     REG::Ux: PartialEq,
@@ -256,6 +326,87 @@ impl<REG> W<REG> where REG: Writable<Safety = Safe> {
     }
 }
 
+impl<REG: WriteFields> W<REG> {
+    /// Writes values of all writable fields.
+    ///
+    /// # Safety
+    ///
+    /// Passing incorrect value can cause undefined behaviour. See reference manual
+    #[inline(always)]
+    pub unsafe fn set_fields_unchecked(&mut self, fields: REG::Fields) -> &mut Self {
+        REG::pack(fields, self)
+    }
+}
+impl<REG> W<REG> where REG: WriteFields<FieldsSafety = Safe> {
+    /// Writes values of all writable fields.
+    #[inline(always)]
+    pub fn set_fields(&mut self, fields: REG::Fields) -> &mut Self {
+        unsafe { REG::pack(fields, self) }
+    }
+}
+
+/// Register values can be built in constant context.
+///
+/// This enables the `W::set_value` method.
+pub trait WriteValue: Writable {}
+
+impl<REG: WriteValue> W<REG> {
+    /// Writes the register value built in constant context.
+    #[inline(always)]
+    pub fn set_value(&mut self, value: Value<REG>) -> &mut Self {
+        self.bits = value.bits;
+        self
+    }
+}
+
+/// Register value.
+///
+/// Built in constant context from the `RESET` constant of the register module with setters of
+/// fields, then written with `W::set_value`.
+pub struct Value<REG: RegisterSpec> {
+    bits: REG::Ux,
+    _reg: marker::PhantomData<REG>,
+}
+
+impl<REG: RegisterSpec> Value<REG> {
+    /// Creates the register value from raw bits.
+    ///
+    /// # Safety
+    ///
+    /// Passing incorrect value can cause undefined behaviour. See reference manual
+    #[inline(always)]
+    pub const unsafe fn from_bits(bits: REG::Ux) -> Self {
+        Self {
+            bits,
+            _reg: marker::PhantomData,
+        }
+    }
+
+    /// Raw bits of the register value.
+    #[inline(always)]
+    pub const fn bits(&self) -> REG::Ux {
+        self.bits
+    }
+}
+
+impl<REG: RegisterSpec> Clone for Value<REG> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<REG: RegisterSpec> Copy for Value<REG> {}
+
+impl<REG: RegisterSpec> core::fmt::Debug for Value<REG>
+where
+    REG::Ux: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&self.bits, f)
+    }
+}
+
 /// Field reader.
 ///
 /// Result of the `read` methods of fields.
@@ -605,3 +756,27 @@ where
         self.w
     }
 }
+
+/// Error of `wait_until_bounded`: the condition didn't hold after the given number of reads
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timeout;
+
+/// Future which is pending once, so other tasks can run between reads of a register
+struct YieldNow(bool);
+
+impl core::future::Future for YieldNow {
+    type Output = ();
+
+    fn poll(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<()> {
+        if self.0 {
+            core::task::Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            core::task::Poll::Pending
+        }
+    }
+}
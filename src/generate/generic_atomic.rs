@@ -1,101 +1,179 @@
-mod atomic {
-    use super::*;
-    use portable_atomic::Ordering;
-
-    pub trait AtomicOperations {
-        unsafe fn atomic_or(ptr: *mut Self, val: Self);
-        unsafe fn atomic_and(ptr: *mut Self, val: Self);
-        unsafe fn atomic_xor(ptr: *mut Self, val: Self);
-    }
+use super::*;
 
-    macro_rules! impl_atomics {
-        ($U:ty, $Atomic:ty) => {
-            impl AtomicOperations for $U {
-                unsafe fn atomic_or(ptr: *mut Self, val: Self) {
-                    (*(ptr as *const $Atomic)).or(val, Ordering::SeqCst);
-                }
+pub trait AtomicOperations {
+    /// The target has atomic operations for this width
+    const SUPPORTED: bool = true;
 
-                unsafe fn atomic_and(ptr: *mut Self, val: Self) {
-                    (*(ptr as *const $Atomic)).and(val, Ordering::SeqCst);
-                }
+    unsafe fn atomic_or(ptr: *mut Self, val: Self);
+    unsafe fn atomic_and(ptr: *mut Self, val: Self);
+    unsafe fn atomic_xor(ptr: *mut Self, val: Self);
+}
 
-                unsafe fn atomic_xor(ptr: *mut Self, val: Self) {
-                    (*(ptr as *const $Atomic)).xor(val, Ordering::SeqCst);
-                }
+/// Atomic operations with atomic types of `core` or `portable-atomic`
+#[allow(unused_macros)]
+macro_rules! impl_atomics {
+    ($U:ty, $Atomic:ty) => {
+        impl AtomicOperations for $U {
+            unsafe fn atomic_or(ptr: *mut Self, val: Self) {
+                (*(ptr as *const $Atomic)).fetch_or(val, core::sync::atomic::Ordering::SeqCst);
             }
-        };
-    }
 
-    impl_atomics!(u8, portable_atomic::AtomicU8);
-    impl_atomics!(u16, portable_atomic::AtomicU16);
+            unsafe fn atomic_and(ptr: *mut Self, val: Self) {
+                (*(ptr as *const $Atomic)).fetch_and(val, core::sync::atomic::Ordering::SeqCst);
+            }
+
+            unsafe fn atomic_xor(ptr: *mut Self, val: Self) {
+                (*(ptr as *const $Atomic)).fetch_xor(val, core::sync::atomic::Ordering::SeqCst);
+            }
+        }
+    };
+}
+
+/// Atomic operations as read-modify-write in a critical section
+#[allow(unused_macros)]
+macro_rules! impl_critical_section {
+    ($U:ty) => {
+        impl AtomicOperations for $U {
+            unsafe fn atomic_or(ptr: *mut Self, val: Self) {
+                critical_section::with(|_| ptr.write_volatile(ptr.read_volatile() | val));
+            }
+
+            unsafe fn atomic_and(ptr: *mut Self, val: Self) {
+                critical_section::with(|_| ptr.write_volatile(ptr.read_volatile() & val));
+            }
+
+            unsafe fn atomic_xor(ptr: *mut Self, val: Self) {
+                critical_section::with(|_| ptr.write_volatile(ptr.read_volatile() ^ val));
+            }
+        }
+    };
+}
+
+/// Width without atomic operations on the target, only registers with aliases can be used
+#[allow(unused_macros)]
+macro_rules! impl_unsupported {
+    ($U:ty) => {
+        impl AtomicOperations for $U {
+            const SUPPORTED: bool = false;
 
-    // Exclude 16-bit archs from 32-bit atomics
-    #[cfg(not(target_pointer_width = "16"))]
-    impl_atomics!(u32, portable_atomic::AtomicU32);
+            unsafe fn atomic_or(_ptr: *mut Self, _val: Self) {
+                unreachable!()
+            }
+
+            unsafe fn atomic_and(_ptr: *mut Self, _val: Self) {
+                unreachable!()
+            }
 
-    // Enable 64-bit atomics for 64-bit RISCV
-    #[cfg(any(target_pointer_width = "64", target_has_atomic = "64"))]
-    impl_atomics!(u64, portable_atomic::AtomicU64);
+            unsafe fn atomic_xor(_ptr: *mut Self, _val: Self) {
+                unreachable!()
+            }
+        }
+    };
+}
+
+/// Compile time checks that the register has an alias or atomic operations for each operation
+struct Check<REG>(marker::PhantomData<REG>);
+
+impl<REG: Writable> Check<REG>
+where
+    REG::Ux: AtomicOperations,
+{
+    const SET: () = assert!(
+        REG::SET_ALIAS.is_some() || REG::Ux::SUPPORTED,
+        "the register has no set alias and the target has no atomic operations for its width"
+    );
+    const CLEAR: () = assert!(
+        REG::CLEAR_ALIAS.is_some() || REG::Ux::SUPPORTED,
+        "the register has no clear alias and the target has no atomic operations for its width"
+    );
+    const TOGGLE: () = assert!(
+        REG::TOGGLE_ALIAS.is_some() || REG::Ux::SUPPORTED,
+        "the register has no toggle alias and the target has no atomic operations for its width"
+    );
+}
 
-    impl<REG: Readable + Writable> Reg<REG>
+impl<REG: Readable + Writable> Reg<REG>
+where
+    REG::Ux: AtomicOperations,
+{
+    /// Pointer to the hardware alias of the register at `offset` bytes
+    #[inline(always)]
+    fn alias(&self, offset: isize) -> *mut REG::Ux {
+        self.as_ptr().cast::<u8>().wrapping_offset(offset).cast()
+    }
+
+    /// Set high every bit in the register that was set in the write proxy. Leave other bits
+    /// untouched. The write is done atomically or as a single write to the set alias of
+    /// the register.
+    ///
+    /// # Safety
+    ///
+    /// The resultant bit pattern may not be valid for the register.
+    #[inline(always)]
+    pub unsafe fn set_bits<F>(&self, f: F)
     where
-        REG::Ux: AtomicOperations
+        F: FnOnce(&mut W<REG>) -> &mut W<REG>,
     {
-        /// Set high every bit in the register that was set in the write proxy. Leave other bits
-        /// untouched. The write is done in a single atomic instruction.
-        ///
-        /// # Safety
-        ///
-        /// The resultant bit pattern may not be valid for the register.
-        #[inline(always)]
-        pub unsafe fn set_bits<F>(&self, f: F)
-        where
-            F: FnOnce(&mut W<REG>) -> &mut W<REG>,
-        {
-            let bits = f(&mut W {
-                bits: Default::default(),
-                _reg: marker::PhantomData,
-            })
-            .bits;
-            REG::Ux::atomic_or(self.register.as_ptr(), bits);
+        let bits = f(&mut W {
+            bits: Default::default(),
+            _reg: marker::PhantomData,
+        })
+        .bits
+        .swap_endian(REG::ENDIAN);
+        let () = Check::<REG>::SET;
+        match REG::SET_ALIAS {
+            Some(offset) => self.alias(offset).write_volatile(bits),
+            None => REG::Ux::atomic_or(self.as_ptr(), bits),
         }
+    }
 
-        /// Clear every bit in the register that was cleared in the write proxy. Leave other bits
-        /// untouched. The write is done in a single atomic instruction.
-        ///
-        /// # Safety
-        ///
-        /// The resultant bit pattern may not be valid for the register.
-        #[inline(always)]
-        pub unsafe fn clear_bits<F>(&self, f: F)
-        where
-            F: FnOnce(&mut W<REG>) -> &mut W<REG>,
-        {
-            let bits = f(&mut W {
-                bits: !REG::Ux::default(),
-                _reg: marker::PhantomData,
-            })
-            .bits;
-            REG::Ux::atomic_and(self.register.as_ptr(), bits);
+    /// Clear every bit in the register that was cleared in the write proxy. Leave other bits
+    /// untouched. The write is done atomically or as a single write to the clear alias of
+    /// the register.
+    ///
+    /// # Safety
+    ///
+    /// The resultant bit pattern may not be valid for the register.
+    #[inline(always)]
+    pub unsafe fn clear_bits<F>(&self, f: F)
+    where
+        F: FnOnce(&mut W<REG>) -> &mut W<REG>,
+    {
+        let bits = f(&mut W {
+            bits: !REG::Ux::default(),
+            _reg: marker::PhantomData,
+        })
+        .bits
+        .swap_endian(REG::ENDIAN);
+        let () = Check::<REG>::CLEAR;
+        match REG::CLEAR_ALIAS {
+            Some(offset) => self.alias(offset).write_volatile(!bits),
+            None => REG::Ux::atomic_and(self.as_ptr(), bits),
         }
+    }
 
-        /// Toggle every bit in the register that was set in the write proxy. Leave other bits
-        /// untouched. The write is done in a single atomic instruction.
-        ///
-        /// # Safety
-        ///
-        /// The resultant bit pattern may not be valid for the register.
-        #[inline(always)]
-        pub unsafe fn toggle_bits<F>(&self, f: F)
-        where
-            F: FnOnce(&mut W<REG>) -> &mut W<REG>,
-        {
-            let bits = f(&mut W {
-                bits: Default::default(),
-                _reg: marker::PhantomData,
-            })
-            .bits;
-            REG::Ux::atomic_xor(self.register.as_ptr(), bits);
+    /// Toggle every bit in the register that was set in the write proxy. Leave other bits
+    /// untouched. The write is done atomically or as a single write to the toggle alias of
+    /// the register.
+    ///
+    /// # Safety
+    ///
+    /// The resultant bit pattern may not be valid for the register.
+    #[inline(always)]
+    pub unsafe fn toggle_bits<F>(&self, f: F)
+    where
+        F: FnOnce(&mut W<REG>) -> &mut W<REG>,
+    {
+        let bits = f(&mut W {
+            bits: Default::default(),
+            _reg: marker::PhantomData,
+        })
+        .bits
+        .swap_endian(REG::ENDIAN);
+        let () = Check::<REG>::TOGGLE;
+        match REG::TOGGLE_ALIAS {
+            Some(offset) => self.alias(offset).write_volatile(bits),
+            None => REG::Ux::atomic_xor(self.as_ptr(), bits),
         }
     }
 }
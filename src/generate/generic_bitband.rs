@@ -0,0 +1,71 @@
+mod bitband {
+    use super::*;
+
+    /// Returns the alias word of bit `bit` of the byte at `address`.
+    ///
+    /// Panics if `address` is outside of the SRAM (`0x2000_0000..0x2010_0000`)
+    /// and peripheral (`0x4000_0000..0x4010_0000`) bit-band regions.
+    #[inline(always)]
+    fn alias(address: usize, bit: usize) -> *mut u32 {
+        let region = address & !0x000F_FFFF;
+        assert!(
+            region == 0x2000_0000 || region == 0x4000_0000,
+            "address is outside of the bit-band regions"
+        );
+        (region + 0x0200_0000 + (address - region) * 32 + bit * 4) as *mut u32
+    }
+
+    impl<REG: Writable> Reg<REG> {
+        /// Writes `value` to bit `bit` of the register through the bit-band alias region.
+        ///
+        /// The bit is changed by a single store, other bits of the register are not changed.
+        ///
+        /// # Safety
+        ///
+        /// The bus reads and writes back the whole register, so it must not have fields
+        /// changed by reading it or by writing their current value (e.g. `oneToClear` flags).
+        #[inline(always)]
+        pub unsafe fn bb_write(&self, bit: u8, value: bool) {
+            let address = self.as_ptr() as usize + bit as usize / 8;
+            alias(address, bit as usize % 8).write_volatile(value as u32);
+        }
+    }
+
+    /// Single-bit field writer through the bit-band alias region
+    pub struct BitBand<REG: RegisterSpec> {
+        ptr: *mut REG::Ux,
+        bit: u8,
+    }
+
+    impl<REG: Writable> BitBand<REG> {
+        /// Creates the writer of bit `bit` of the register
+        #[inline(always)]
+        pub fn new(reg: &Reg<REG>, bit: u8) -> Self {
+            Self {
+                ptr: reg.as_ptr(),
+                bit,
+            }
+        }
+
+        /// Writes bit to the field
+        #[inline(always)]
+        pub fn bit(self, value: bool) {
+            let address = self.ptr as usize + self.bit as usize / 8;
+            unsafe { alias(address, self.bit as usize % 8).write_volatile(value as u32) }
+        }
+
+        /// Sets the field bit
+        #[inline(always)]
+        pub fn set_bit(self) {
+            self.bit(true)
+        }
+
+        /// Clears the field bit
+        #[inline(always)]
+        pub fn clear_bit(self) {
+            self.bit(false)
+        }
+    }
+}
+
+pub use bitband::BitBand;
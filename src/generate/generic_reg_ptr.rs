@@ -0,0 +1,413 @@
+/// This structure provides volatile access to registers through a raw pointer.
+///
+/// It is created by accessors of register blocks and never makes references to the register memory.
+pub struct Reg<REG: RegisterSpec> {
+    ptr: *mut REG::Ux,
+    _marker: marker::PhantomData<REG>,
+}
+
+impl<REG: RegisterSpec> Clone for Reg<REG> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<REG: RegisterSpec> Copy for Reg<REG> {}
+
+unsafe impl<REG: RegisterSpec> Send for Reg<REG> where REG::Ux: Send {}
+
+impl<REG: RegisterSpec> Reg<REG> {
+    /// Creates the register at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be the address of a register of this type.
+    #[inline(always)]
+    pub const unsafe fn from_ptr(ptr: *mut u8) -> Self {
+        Self {
+            ptr: ptr.cast(),
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Returns the underlying memory address of register.
+    ///
+    /// ```ignore
+    /// let reg_ptr = periph.reg().as_ptr();
+    /// ```
+    #[inline(always)]
+    pub const fn as_ptr(&self) -> *mut REG::Ux {
+        self.ptr
+    }
+
+    #[inline(always)]
+    fn get(&self) -> REG::Ux {
+        unsafe { self.ptr.read_volatile().swap_endian(REG::ENDIAN) }
+    }
+
+    #[inline(always)]
+    fn set(&self, bits: REG::Ux) {
+        unsafe { self.ptr.write_volatile(bits.swap_endian(REG::ENDIAN)) }
+    }
+}
+
+impl<REG: Readable> Reg<REG> {
+    /// Reads the contents of a `Readable` register.
+    ///
+    /// You can read the raw contents of a register by using `bits`:
+    /// ```ignore
+    /// let bits = periph.reg().read().bits();
+    /// ```
+    /// or get the content of a particular field of a register:
+    /// ```ignore
+    /// let reader = periph.reg().read();
+    /// let bits = reader.field1().bits();
+    /// let flag = reader.field2().bit_is_set();
+    /// ```
+    #[inline(always)]
+    pub fn read(&self) -> R<REG> {
+        R {
+            bits: self.get(),
+            _reg: marker::PhantomData,
+        }
+    }
+}
+
+impl<REG: Readable> Reg<REG> {
+    /// Reads the register until `f` returns `true` for the value and returns the value.
+    ///
+    /// ```ignore
+    /// periph.sr().wait_until(|r| r.busy().bit_is_clear());
+    /// ```
+    #[inline(always)]
+    pub fn wait_until<F>(&self, mut f: F) -> R<REG>
+    where
+        F: FnMut(&R<REG>) -> bool,
+    {
+        loop {
+            let r = self.read();
+            if f(&r) {
+                return r;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Reads the register until `f` returns `true` for the value, at most `max_reads` times.
+    ///
+    /// ```ignore
+    /// let r = periph.sr().wait_until_bounded(|r| r.ready().bit_is_set(), 1000)?;
+    /// ```
+    #[inline(always)]
+    pub fn wait_until_bounded<F>(&self, mut f: F, max_reads: usize) -> Result<R<REG>, Timeout>
+    where
+        F: FnMut(&R<REG>) -> bool,
+    {
+        for _ in 0..max_reads {
+            let r = self.read();
+            if f(&r) {
+                return Ok(r);
+            }
+            core::hint::spin_loop();
+        }
+        Err(Timeout)
+    }
+
+    /// Reads the register until `f` returns `true` for the value, yielding to other tasks
+    /// between reads.
+    ///
+    /// ```ignore
+    /// periph.sr().wait_until_async(|r| r.busy().bit_is_clear()).await;
+    /// ```
+    pub async fn wait_until_async<F>(&self, mut f: F) -> R<REG>
+    where
+        F: FnMut(&R<REG>) -> bool,
+    {
+        loop {
+            let r = self.read();
+            if f(&r) {
+                return r;
+            }
+            YieldNow(false).await;
+        }
+    }
+}
+
+impl<REG: Resettable + Writable> Reg<REG> {
+    /// Writes the reset value to `Writable` register.
+    ///
+    /// Resets the register to its initial state.
+    #[inline(always)]
+    pub fn reset(&self) {
+        self.set(REG::RESET_VALUE)
+    }
+
+    /// Writes bits to a `Writable` register.
+    ///
+    /// You can write raw bits into a register:
+    /// ```ignore
+    /// periph.reg().write(|w| unsafe { w.bits(rawbits) });
+    /// ```
+    /// or write only the fields you need:
+    /// ```ignore
+    /// periph.reg().write(|w| w
+    ///     .field1().bits(newfield1bits)
+    ///     .field2().set_bit()
+    ///     .field3().variant(VARIANT)
+    /// );
+    /// ```
+    /// or an alternative way of saying the same:
+    /// ```ignore
+    /// periph.reg().write(|w| {
+    ///     w.field1().bits(newfield1bits);
+    ///     w.field2().set_bit();
+    ///     w.field3().variant(VARIANT)
+    /// });
+    /// ```
+    /// In the latter case, other fields will be set to their reset value.
+    #[inline(always)]
+    pub fn write<F>(&self, f: F) -> REG::Ux
+    where
+        F: FnOnce(&mut W<REG>) -> &mut W<REG>,
+    {
+        let value = f(&mut W {
+            bits: REG::RESET_VALUE & !REG::ONE_TO_MODIFY_FIELDS_BITMAP
+                | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
+            _reg: marker::PhantomData,
+        })
+        .bits;
+        self.set(value);
+        value
+    }
+
+    /// Writes bits to a `Writable` register and produce a value.
+    ///
+    /// You can write raw bits into a register:
+    /// ```ignore
+    /// periph.reg().write_and(|w| unsafe { w.bits(rawbits); });
+    /// ```
+    /// or write only the fields you need:
+    /// ```ignore
+    /// periph.reg().write_and(|w| {
+    ///     w.field1().bits(newfield1bits)
+    ///         .field2().set_bit()
+    ///         .field3().variant(VARIANT);
+    /// });
+    /// ```
+    /// or an alternative way of saying the same:
+    /// ```ignore
+    /// periph.reg().write_and(|w| {
+    ///     w.field1().bits(newfield1bits);
+    ///     w.field2().set_bit();
+    ///     w.field3().variant(VARIANT);
+    /// });
+    /// ```
+    /// In the latter case, other fields will be set to their reset value.
+    ///
+    /// Values can be returned from the closure:
+    /// ```ignore
+    /// let state = periph.reg().write_and(|w| State::set(w.field1()));
+    /// ```
+    #[inline(always)]
+    pub fn from_write<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut W<REG>) -> T,
+    {
+        let mut writer = W {
+            bits: REG::RESET_VALUE & !REG::ONE_TO_MODIFY_FIELDS_BITMAP
+                | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
+            _reg: marker::PhantomData,
+        };
+        let result = f(&mut writer);
+
+        self.set(writer.bits);
+
+        result
+    }
+}
+
+impl<REG: Writable> Reg<REG> {
+    /// Writes 0 to a `Writable` register.
+    ///
+    /// Similar to `write`, but unused bits will contain 0.
+    ///
+    /// # Safety
+    ///
+    /// Unsafe to use with registers which don't allow to write 0.
+    #[inline(always)]
+    pub unsafe fn write_with_zero<F>(&self, f: F) -> REG::Ux
+    where
+        F: FnOnce(&mut W<REG>) -> &mut W<REG>,
+    {
+        let value = f(&mut W {
+            bits: REG::Ux::default(),
+            _reg: marker::PhantomData,
+        })
+        .bits;
+        self.set(value);
+        value
+    }
+
+    /// Writes 0 to a `Writable` register and produces a value.
+    ///
+    /// Similar to `write`, but unused bits will contain 0.
+    ///
+    /// # Safety
+    ///
+    /// Unsafe to use with registers which don't allow to write 0.
+    #[inline(always)]
+    pub unsafe fn from_write_with_zero<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut W<REG>) -> T,
+    {
+        let mut writer = W {
+            bits: REG::Ux::default(),
+            _reg: marker::PhantomData,
+        };
+
+        let result = f(&mut writer);
+
+        self.set(writer.bits);
+
+        result
+    }
+}
+
+impl<REG: Readable + Writable> Reg<REG> {
+    /// Modifies the contents of the register by reading and then writing it.
+    ///
+    /// E.g. to do a read-modify-write sequence to change parts of a register:
+    /// ```ignore
+    /// periph.reg().modify(|r, w| unsafe { w.bits(
+    ///    r.bits() | 3
+    /// ) });
+    /// ```
+    /// or
+    /// ```ignore
+    /// periph.reg().modify(|_, w| w
+    ///     .field1().bits(newfield1bits)
+    ///     .field2().set_bit()
+    ///     .field3().variant(VARIANT)
+    /// );
+    /// ```
+    /// or an alternative way of saying the same:
+    /// ```ignore
+    /// periph.reg().modify(|_, w| {
+    ///     w.field1().bits(newfield1bits);
+    ///     w.field2().set_bit();
+    ///     w.field3().variant(VARIANT)
+    /// });
+    /// ```
+    /// Other fields will have the value they had before the call to `modify`.
+    #[inline(always)]
+    pub fn modify<F>(&self, f: F) -> REG::Ux
+    where
+        for<'w> F: FnOnce(&R<REG>, &'w mut W<REG>) -> &'w mut W<REG>,
+    {
+        let bits = self.get();
+        let value = f(
+            &R {
+                bits,
+                _reg: marker::PhantomData,
+            },
+            &mut W {
+                bits: bits & !REG::ONE_TO_MODIFY_FIELDS_BITMAP | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
+                _reg: marker::PhantomData,
+            },
+        )
+        .bits;
+        self.set(value);
+        value
+    }
+
+    /// Modifies the contents of the register by reading and then writing it
+    /// and produces a value.
+    ///
+    /// E.g. to do a read-modify-write sequence to change parts of a register:
+    /// ```ignore
+    /// let bits = periph.reg().modify(|r, w| {
+    ///     let new_bits = r.bits() | 3;
+    ///     unsafe {
+    ///         w.bits(new_bits);
+    ///     }
+    ///
+    ///     new_bits
+    /// });
+    /// ```
+    /// or
+    /// ```ignore
+    /// periph.reg().modify(|_, w| {
+    ///     w.field1().bits(newfield1bits)
+    ///         .field2().set_bit()
+    ///         .field3().variant(VARIANT);
+    /// });
+    /// ```
+    /// or an alternative way of saying the same:
+    /// ```ignore
+    /// periph.reg().modify(|_, w| {
+    ///     w.field1().bits(newfield1bits);
+    ///     w.field2().set_bit();
+    ///     w.field3().variant(VARIANT);
+    /// });
+    /// ```
+    /// Other fields will have the value they had before the call to `modify`.
+    #[inline(always)]
+    pub fn from_modify<F, T>(&self, f: F) -> T
+    where
+        for<'w> F: FnOnce(&R<REG>, &'w mut W<REG>) -> T,
+    {
+        let bits = self.get();
+
+        let mut writer = W {
+            bits: bits & !REG::ONE_TO_MODIFY_FIELDS_BITMAP | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
+            _reg: marker::PhantomData,
+        };
+
+        let result = f(
+            &R {
+                bits,
+                _reg: marker::PhantomData,
+            },
+            &mut writer,
+        );
+
+        self.set(writer.bits);
+
+        result
+    }
+}
+
+impl<REG: ReadFields> Reg<REG> {
+    /// Reads values of all fields of the register.
+    ///
+    /// ```ignore
+    /// let config = periph.cr().read_value();
+    /// ```
+    #[inline(always)]
+    pub fn read_value(&self) -> REG::Fields {
+        self.read().fields()
+    }
+}
+
+impl<REG: Resettable + WriteFields<FieldsSafety = Safe>> Reg<REG> {
+    /// Writes values of all fields to the register.
+    ///
+    /// ```ignore
+    /// periph.cr().write_value(cr::Fields { en: true, ..config });
+    /// ```
+    /// Other bits will be set to their reset value.
+    #[inline(always)]
+    pub fn write_value(&self, fields: REG::Fields) -> REG::Ux {
+        self.write(|w| w.set_fields(fields))
+    }
+}
+
+impl<REG: Readable> core::fmt::Debug for crate::generic::Reg<REG>
+where
+    R<REG>: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&self.read(), f)
+    }
+}
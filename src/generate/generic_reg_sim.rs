@@ -0,0 +1,505 @@
+extern crate std;
+
+/// Simulated registers for testing drivers on the host.
+///
+/// Peripherals dereference to zeroed host memory standing in for their register blocks, see
+/// [`block`]. State of each register is kept in a thread-local map keyed by the address of the
+/// register, so tests running in parallel threads don't interfere. Neither the host memory nor
+/// the memory at the addresses of peripherals is ever accessed.
+pub mod sim {
+    use super::std::any::TypeId;
+    use super::std::cell::RefCell;
+    use super::std::collections::{BTreeMap, HashMap, VecDeque};
+    use super::std::sync::Mutex;
+    use super::std::vec::Vec;
+
+    /// Access to a simulated register
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Access<U> {
+        /// Value returned by a read
+        Read(U),
+        /// Value passed to a write, before modified write values of fields are applied
+        Write(U),
+    }
+
+    /// Initial state of a simulated register
+    pub trait Simulated: super::RegisterSpec {
+        /// Value of the register before the first write: the reset value from SVD or 0
+        const INITIAL_VALUE: Self::Ux;
+        /// Bits of `oneToSet` fields
+        const ONE_TO_SET: u64 = 0;
+        /// Bits of `oneToClear` fields
+        const ONE_TO_CLEAR: u64 = 0;
+        /// Bits of `oneToToggle` fields
+        const ONE_TO_TOGGLE: u64 = 0;
+        /// Bits of `zeroToSet` fields
+        const ZERO_TO_SET: u64 = 0;
+        /// Bits of `zeroToClear` fields
+        const ZERO_TO_CLEAR: u64 = 0;
+        /// Bits of `zeroToToggle` fields
+        const ZERO_TO_TOGGLE: u64 = 0;
+        /// Bits of `set` fields, set to 1 by any write
+        const WRITE_SET: u64 = 0;
+        /// Bits of `clear` fields, cleared by any write
+        const WRITE_CLEAR: u64 = 0;
+    }
+
+    /// Value of the register after writing `bits` when it had value `old`
+    pub(super) fn written<REG: Simulated>(old: u64, bits: u64) -> u64 {
+        let modified = REG::ONE_TO_SET
+            | REG::ONE_TO_CLEAR
+            | REG::ONE_TO_TOGGLE
+            | REG::ZERO_TO_SET
+            | REG::ZERO_TO_CLEAR
+            | REG::ZERO_TO_TOGGLE
+            | REG::WRITE_SET
+            | REG::WRITE_CLEAR;
+        bits & !modified
+            | (old | bits) & REG::ONE_TO_SET
+            | old & !bits & REG::ONE_TO_CLEAR
+            | (old ^ bits) & REG::ONE_TO_TOGGLE
+            | (old | !bits) & REG::ZERO_TO_SET
+            | old & bits & REG::ZERO_TO_CLEAR
+            | (old ^ !bits) & REG::ZERO_TO_TOGGLE
+            | REG::WRITE_SET
+    }
+
+    /// Conversion of raw register values to the storage of the simulator
+    pub trait Bits: Copy {
+        fn to_u64(self) -> u64;
+        fn from_u64(value: u64) -> Self;
+    }
+
+    macro_rules! bits {
+        ($U:ty) => {
+            impl Bits for $U {
+                #[inline(always)]
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+                #[inline(always)]
+                fn from_u64(value: u64) -> Self {
+                    value as $U
+                }
+            }
+        };
+    }
+
+    bits!(u8);
+    bits!(u16);
+    bits!(u32);
+    bits!(u64);
+
+    #[derive(Default)]
+    pub(super) struct State {
+        pub(super) value: Option<u64>,
+        pub(super) reads: VecDeque<u64>,
+        pub(super) accesses: Vec<Access<u64>>,
+    }
+
+    super::std::thread_local! {
+        static REGISTERS: RefCell<HashMap<usize, State>> = RefCell::new(HashMap::new());
+    }
+
+    pub(super) fn with<T>(address: usize, f: impl FnOnce(&mut State) -> T) -> T {
+        REGISTERS.with(|registers| f(registers.borrow_mut().entry(address).or_default()))
+    }
+
+    /// Forgets values, injected reads and recorded accesses of all the registers of this thread
+    pub fn reset_all() {
+        REGISTERS.with(|registers| registers.borrow_mut().clear())
+    }
+
+    static BLOCKS: Mutex<BTreeMap<(usize, TypeId), usize>> = Mutex::new(BTreeMap::new());
+
+    /// Register block `T` of the peripheral at `address`.
+    ///
+    /// The block is zeroed host memory allocated on first use and shared by all the threads,
+    /// so references to simulated registers never point to unmapped memory. Its contents are
+    /// never read or written, only the addresses of its registers are used.
+    pub fn block<T: 'static>(address: usize) -> &'static T {
+        let layout = super::std::alloc::Layout::new::<T>();
+        if layout.size() == 0 {
+            return unsafe { &*core::ptr::NonNull::<T>::dangling().as_ptr() };
+        }
+        let mut blocks = BLOCKS.lock().unwrap_or_else(|e| e.into_inner());
+        let block = *blocks
+            .entry((address, TypeId::of::<T>()))
+            .or_insert_with(|| {
+                let block = unsafe { super::std::alloc::alloc_zeroed(layout) };
+                if block.is_null() {
+                    super::std::alloc::handle_alloc_error(layout);
+                }
+                block as usize
+            });
+        unsafe { &*(block as *const T) }
+    }
+}
+
+/// This structure provides simulated access to registers.
+#[repr(transparent)]
+pub struct Reg<REG: RegisterSpec> {
+    register: core::cell::UnsafeCell<REG::Ux>,
+    _marker: marker::PhantomData<REG>,
+}
+
+unsafe impl<REG: RegisterSpec> Send for Reg<REG> where REG::Ux: Send {}
+
+impl<REG: RegisterSpec> Reg<REG> {
+    /// Returns the underlying memory address of register.
+    ///
+    /// ```ignore
+    /// let reg_ptr = periph.reg.as_ptr();
+    /// ```
+    #[inline(always)]
+    pub fn as_ptr(&self) -> *mut REG::Ux {
+        self.register.get()
+    }
+
+    #[inline(always)]
+    fn address(&self) -> usize {
+        self as *const Self as usize
+    }
+}
+
+impl<REG: sim::Simulated> Reg<REG>
+where
+    REG::Ux: sim::Bits,
+{
+    /// Sets the value of the register without recording an access.
+    pub fn sim_preload(&self, bits: REG::Ux) {
+        sim::with(self.address(), |s| s.value = Some(sim::Bits::to_u64(bits)))
+    }
+
+    /// Value of the register without recording an access.
+    pub fn sim_value(&self) -> REG::Ux {
+        sim::with(self.address(), |s| {
+            s.value
+                .map_or(REG::INITIAL_VALUE, <REG::Ux as sim::Bits>::from_u64)
+        })
+    }
+
+    /// Makes next reads return `values` in order. The value of the register is not changed.
+    ///
+    /// ```ignore
+    /// // busy for two reads
+    /// periph.sr().sim_inject_reads([0b1, 0b1]);
+    /// ```
+    pub fn sim_inject_reads(&self, values: impl IntoIterator<Item = REG::Ux>) {
+        sim::with(self.address(), |s| {
+            s.reads.extend(values.into_iter().map(sim::Bits::to_u64))
+        })
+    }
+
+    /// Returns and forgets recorded reads and writes of the register.
+    pub fn sim_take_accesses(&self) -> std::vec::Vec<sim::Access<REG::Ux>> {
+        sim::with(self.address(), |s| {
+            s.accesses
+                .drain(..)
+                .map(|a| match a {
+                    sim::Access::Read(v) => sim::Access::Read(sim::Bits::from_u64(v)),
+                    sim::Access::Write(v) => sim::Access::Write(sim::Bits::from_u64(v)),
+                })
+                .collect()
+        })
+    }
+
+    fn get(&self) -> REG::Ux {
+        sim::with(self.address(), |s| {
+            let value = s
+                .reads
+                .pop_front()
+                .or(s.value)
+                .unwrap_or(sim::Bits::to_u64(REG::INITIAL_VALUE));
+            s.accesses.push(sim::Access::Read(value));
+            sim::Bits::from_u64(value)
+        })
+    }
+}
+
+impl<REG: sim::Simulated + Writable> Reg<REG>
+where
+    REG::Ux: sim::Bits,
+{
+    /// Stores written bits. Fields with modified write values are set, cleared or toggled
+    /// as described in SVD, other bits are replaced.
+    fn set(&self, bits: REG::Ux) {
+        use sim::Bits;
+        let bits = bits.to_u64();
+        sim::with(self.address(), |s| {
+            let old = s.value.unwrap_or(REG::INITIAL_VALUE.to_u64());
+            let value = REG::Ux::from_u64(sim::written::<REG>(old, bits));
+            s.value = Some(value.to_u64());
+            s.accesses.push(sim::Access::Write(bits));
+        })
+    }
+}
+
+impl<REG: Readable + sim::Simulated> Reg<REG>
+where
+    REG::Ux: sim::Bits,
+{
+    /// Reads the contents of a `Readable` register.
+    ///
+    /// Returns the next injected value or the value of the register.
+    #[inline(always)]
+    pub fn read(&self) -> R<REG> {
+        R {
+            bits: self.get(),
+            _reg: marker::PhantomData,
+        }
+    }
+}
+
+impl<REG: Readable + sim::Simulated> Reg<REG>
+where
+    REG::Ux: sim::Bits,
+{
+    /// Reads the register until `f` returns `true` for the value and returns the value.
+    ///
+    /// ```ignore
+    /// periph.sr().wait_until(|r| r.busy().bit_is_clear());
+    /// ```
+    #[inline(always)]
+    pub fn wait_until<F>(&self, mut f: F) -> R<REG>
+    where
+        F: FnMut(&R<REG>) -> bool,
+    {
+        loop {
+            let r = self.read();
+            if f(&r) {
+                return r;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Reads the register until `f` returns `true` for the value, at most `max_reads` times.
+    ///
+    /// ```ignore
+    /// let r = periph.sr().wait_until_bounded(|r| r.ready().bit_is_set(), 1000)?;
+    /// ```
+    #[inline(always)]
+    pub fn wait_until_bounded<F>(&self, mut f: F, max_reads: usize) -> Result<R<REG>, Timeout>
+    where
+        F: FnMut(&R<REG>) -> bool,
+    {
+        for _ in 0..max_reads {
+            let r = self.read();
+            if f(&r) {
+                return Ok(r);
+            }
+            core::hint::spin_loop();
+        }
+        Err(Timeout)
+    }
+
+    /// Reads the register until `f` returns `true` for the value, yielding to other tasks
+    /// between reads.
+    ///
+    /// ```ignore
+    /// periph.sr().wait_until_async(|r| r.busy().bit_is_clear()).await;
+    /// ```
+    pub async fn wait_until_async<F>(&self, mut f: F) -> R<REG>
+    where
+        F: FnMut(&R<REG>) -> bool,
+    {
+        loop {
+            let r = self.read();
+            if f(&r) {
+                return r;
+            }
+            YieldNow(false).await;
+        }
+    }
+}
+
+impl<REG: Resettable + Writable + sim::Simulated> Reg<REG>
+where
+    REG::Ux: sim::Bits,
+{
+    /// Writes the reset value to `Writable` register.
+    #[inline(always)]
+    pub fn reset(&self) {
+        self.set(REG::RESET_VALUE)
+    }
+
+    /// Writes bits to a `Writable` register.
+    ///
+    /// Other fields will be set to their reset value.
+    #[inline(always)]
+    pub fn write<F>(&self, f: F) -> REG::Ux
+    where
+        F: FnOnce(&mut W<REG>) -> &mut W<REG>,
+    {
+        let value = f(&mut W {
+            bits: REG::RESET_VALUE & !REG::ONE_TO_MODIFY_FIELDS_BITMAP
+                | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
+            _reg: marker::PhantomData,
+        })
+        .bits;
+        self.set(value);
+        value
+    }
+
+    /// Writes bits to a `Writable` register and produce a value.
+    #[inline(always)]
+    pub fn from_write<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut W<REG>) -> T,
+    {
+        let mut writer = W {
+            bits: REG::RESET_VALUE & !REG::ONE_TO_MODIFY_FIELDS_BITMAP
+                | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
+            _reg: marker::PhantomData,
+        };
+        let result = f(&mut writer);
+
+        self.set(writer.bits);
+
+        result
+    }
+}
+
+impl<REG: Writable + sim::Simulated> Reg<REG>
+where
+    REG::Ux: sim::Bits,
+{
+    /// Writes 0 to a `Writable` register.
+    ///
+    /// # Safety
+    ///
+    /// Unsafe to use with registers which don't allow to write 0.
+    #[inline(always)]
+    pub unsafe fn write_with_zero<F>(&self, f: F) -> REG::Ux
+    where
+        F: FnOnce(&mut W<REG>) -> &mut W<REG>,
+    {
+        let value = f(&mut W {
+            bits: REG::Ux::default(),
+            _reg: marker::PhantomData,
+        })
+        .bits;
+        self.set(value);
+        value
+    }
+
+    /// Writes 0 to a `Writable` register and produces a value.
+    ///
+    /// # Safety
+    ///
+    /// Unsafe to use with registers which don't allow to write 0.
+    #[inline(always)]
+    pub unsafe fn from_write_with_zero<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut W<REG>) -> T,
+    {
+        let mut writer = W {
+            bits: REG::Ux::default(),
+            _reg: marker::PhantomData,
+        };
+
+        let result = f(&mut writer);
+
+        self.set(writer.bits);
+
+        result
+    }
+}
+
+impl<REG: Readable + Writable + sim::Simulated> Reg<REG>
+where
+    REG::Ux: sim::Bits,
+{
+    /// Modifies the contents of the register by reading and then writing it.
+    ///
+    /// Other fields will have the value they had before the call to `modify`.
+    #[inline(always)]
+    pub fn modify<F>(&self, f: F) -> REG::Ux
+    where
+        for<'w> F: FnOnce(&R<REG>, &'w mut W<REG>) -> &'w mut W<REG>,
+    {
+        let bits = self.get();
+        let value = f(
+            &R {
+                bits,
+                _reg: marker::PhantomData,
+            },
+            &mut W {
+                bits: bits & !REG::ONE_TO_MODIFY_FIELDS_BITMAP | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
+                _reg: marker::PhantomData,
+            },
+        )
+        .bits;
+        self.set(value);
+        value
+    }
+
+    /// Modifies the contents of the register by reading and then writing it
+    /// and produces a value.
+    #[inline(always)]
+    pub fn from_modify<F, T>(&self, f: F) -> T
+    where
+        for<'w> F: FnOnce(&R<REG>, &'w mut W<REG>) -> T,
+    {
+        let bits = self.get();
+
+        let mut writer = W {
+            bits: bits & !REG::ONE_TO_MODIFY_FIELDS_BITMAP | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
+            _reg: marker::PhantomData,
+        };
+
+        let result = f(
+            &R {
+                bits,
+                _reg: marker::PhantomData,
+            },
+            &mut writer,
+        );
+
+        self.set(writer.bits);
+
+        result
+    }
+}
+
+impl<REG: ReadFields + sim::Simulated> Reg<REG>
+where
+    REG::Ux: sim::Bits,
+{
+    /// Reads values of all fields of the register.
+    ///
+    /// ```ignore
+    /// let config = periph.cr().read_value();
+    /// ```
+    #[inline(always)]
+    pub fn read_value(&self) -> REG::Fields {
+        self.read().fields()
+    }
+}
+
+impl<REG: Resettable + WriteFields<FieldsSafety = Safe> + sim::Simulated> Reg<REG>
+where
+    REG::Ux: sim::Bits,
+{
+    /// Writes values of all fields to the register.
+    ///
+    /// ```ignore
+    /// periph.cr().write_value(cr::Fields { en: true, ..config });
+    /// ```
+    /// Other bits will be set to their reset value.
+    #[inline(always)]
+    pub fn write_value(&self, fields: REG::Fields) -> REG::Ux {
+        self.write(|w| w.set_fields(fields))
+    }
+}
+
+impl<REG: Readable + sim::Simulated> core::fmt::Debug for crate::generic::Reg<REG>
+where
+    REG::Ux: sim::Bits,
+    R<REG>: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&self.read(), f)
+    }
+}
@@ -0,0 +1,404 @@
+/// Access to registers of devices behind a bus (I2C, SPI, ...)
+pub mod transport {
+    /// Bus to the device, implemented by the driver.
+    ///
+    /// `address` is the address of the register in SVD address units
+    /// ([`ADDRESS_UNIT_BITS`](crate::ADDRESS_UNIT_BITS)), `size` is the size of the register in bits.
+    pub trait Interface {
+        /// Error of the bus
+        type Error;
+
+        /// Reads the register
+        fn read_register(&mut self, address: u64, size: u32) -> Result<u64, Self::Error>;
+
+        /// Writes `value` to the register
+        fn write_register(
+            &mut self,
+            address: u64,
+            size: u32,
+            value: u64,
+        ) -> Result<(), Self::Error>;
+    }
+
+    /// Asynchronous bus to the device, implemented by the driver.
+    ///
+    /// Arguments are the same as in [`Interface`].
+    pub trait AsyncInterface {
+        /// Error of the bus
+        type Error;
+
+        /// Reads the register
+        fn read_register(
+            &mut self,
+            address: u64,
+            size: u32,
+        ) -> impl core::future::Future<Output = Result<u64, Self::Error>>;
+
+        /// Writes `value` to the register
+        fn write_register(
+            &mut self,
+            address: u64,
+            size: u32,
+            value: u64,
+        ) -> impl core::future::Future<Output = Result<(), Self::Error>>;
+    }
+
+    /// Size of the register from SVD
+    pub trait RegisterSize: super::RegisterSpec {
+        /// Size of the register in bits
+        const SIZE: u32;
+    }
+
+    /// Conversion of raw register values to values of the bus
+    pub trait Bits: Copy {
+        fn to_u64(self) -> u64;
+        fn from_u64(value: u64) -> Self;
+    }
+
+    macro_rules! bits {
+        ($U:ty) => {
+            impl Bits for $U {
+                #[inline(always)]
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+                #[inline(always)]
+                fn from_u64(value: u64) -> Self {
+                    value as $U
+                }
+            }
+        };
+    }
+
+    bits!(u8);
+    bits!(u16);
+    bits!(u32);
+    bits!(u64);
+}
+
+use transport::{AsyncInterface, Bits, Interface, RegisterSize};
+
+/// This structure provides access to registers through an [`Interface`] of the bus.
+///
+/// It holds the address of the register and is created by accessors of register blocks.
+pub struct Reg<REG: RegisterSpec> {
+    addr: u64,
+    _marker: marker::PhantomData<REG>,
+}
+
+impl<REG: RegisterSpec> Clone for Reg<REG> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<REG: RegisterSpec> Copy for Reg<REG> {}
+
+unsafe impl<REG: RegisterSpec> Send for Reg<REG> {}
+
+impl<REG: RegisterSpec> Reg<REG> {
+    /// Creates the register at bus address `addr`.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be the address of a register of this type on the bus.
+    #[inline(always)]
+    pub const unsafe fn from_addr(addr: u64) -> Self {
+        Self {
+            addr,
+            _marker: marker::PhantomData,
+        }
+    }
+
+    /// Returns the address of the register in SVD address units.
+    #[inline(always)]
+    pub const fn addr(&self) -> u64 {
+        self.addr
+    }
+}
+
+impl<REG: Readable + RegisterSize> Reg<REG>
+where
+    REG::Ux: Bits,
+{
+    /// Reads the contents of a `Readable` register.
+    ///
+    /// ```ignore
+    /// let reader = dev.status().read(&mut bus)?;
+    /// let flag = reader.ready().bit_is_set();
+    /// ```
+    #[inline(always)]
+    pub fn read<I: Interface>(&self, bus: &mut I) -> Result<R<REG>, I::Error> {
+        Ok(R {
+            bits: REG::Ux::from_u64(bus.read_register(self.addr, REG::SIZE)?),
+            _reg: marker::PhantomData,
+        })
+    }
+
+    /// Reads the contents of a `Readable` register.
+    #[inline(always)]
+    pub async fn read_async<I: AsyncInterface>(&self, bus: &mut I) -> Result<R<REG>, I::Error> {
+        Ok(R {
+            bits: REG::Ux::from_u64(bus.read_register(self.addr, REG::SIZE).await?),
+            _reg: marker::PhantomData,
+        })
+    }
+}
+
+impl<REG: Readable + RegisterSize> Reg<REG>
+where
+    REG::Ux: Bits,
+{
+    /// Reads the register until `f` returns `true` for the value and returns the value.
+    ///
+    /// ```ignore
+    /// dev.status().wait_until(&mut bus, |r| r.busy().bit_is_clear())?;
+    /// ```
+    #[inline(always)]
+    pub fn wait_until<I, F>(&self, bus: &mut I, mut f: F) -> Result<R<REG>, I::Error>
+    where
+        I: Interface,
+        F: FnMut(&R<REG>) -> bool,
+    {
+        loop {
+            let r = self.read(bus)?;
+            if f(&r) {
+                return Ok(r);
+            }
+        }
+    }
+
+    /// Reads the register until `f` returns `true` for the value, at most `max_reads` times.
+    ///
+    /// Errors of the bus are converted from [`Timeout`] when the condition doesn't hold.
+    #[inline(always)]
+    pub fn wait_until_bounded<I, F>(
+        &self,
+        bus: &mut I,
+        mut f: F,
+        max_reads: usize,
+    ) -> Result<R<REG>, I::Error>
+    where
+        I: Interface,
+        I::Error: From<Timeout>,
+        F: FnMut(&R<REG>) -> bool,
+    {
+        for _ in 0..max_reads {
+            let r = self.read(bus)?;
+            if f(&r) {
+                return Ok(r);
+            }
+        }
+        Err(Timeout.into())
+    }
+
+    /// Reads the register until `f` returns `true` for the value, yielding to other tasks
+    /// between reads.
+    pub async fn wait_until_async<I, F>(&self, bus: &mut I, mut f: F) -> Result<R<REG>, I::Error>
+    where
+        I: AsyncInterface,
+        F: FnMut(&R<REG>) -> bool,
+    {
+        loop {
+            let r = self.read_async(bus).await?;
+            if f(&r) {
+                return Ok(r);
+            }
+            YieldNow(false).await;
+        }
+    }
+}
+
+impl<REG: Resettable + Writable + RegisterSize> Reg<REG>
+where
+    REG::Ux: Bits,
+{
+    #[inline(always)]
+    fn writer() -> W<REG> {
+        W {
+            bits: REG::RESET_VALUE & !REG::ONE_TO_MODIFY_FIELDS_BITMAP
+                | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
+            _reg: marker::PhantomData,
+        }
+    }
+
+    /// Writes the reset value to `Writable` register.
+    #[inline(always)]
+    pub fn reset<I: Interface>(&self, bus: &mut I) -> Result<(), I::Error> {
+        bus.write_register(self.addr, REG::SIZE, REG::RESET_VALUE.to_u64())
+    }
+
+    /// Writes the reset value to `Writable` register.
+    #[inline(always)]
+    pub async fn reset_async<I: AsyncInterface>(&self, bus: &mut I) -> Result<(), I::Error> {
+        bus.write_register(self.addr, REG::SIZE, REG::RESET_VALUE.to_u64())
+            .await
+    }
+
+    /// Writes bits to a `Writable` register.
+    ///
+    /// ```ignore
+    /// dev.ctrl().write(&mut bus, |w| w.mode().bits(2).en().set_bit())?;
+    /// ```
+    /// Other fields will be set to their reset value.
+    #[inline(always)]
+    pub fn write<I, F>(&self, bus: &mut I, f: F) -> Result<REG::Ux, I::Error>
+    where
+        I: Interface,
+        F: FnOnce(&mut W<REG>) -> &mut W<REG>,
+    {
+        let value = f(&mut Self::writer()).bits;
+        bus.write_register(self.addr, REG::SIZE, value.to_u64())?;
+        Ok(value)
+    }
+
+    /// Writes bits to a `Writable` register.
+    #[inline(always)]
+    pub async fn write_async<I, F>(&self, bus: &mut I, f: F) -> Result<REG::Ux, I::Error>
+    where
+        I: AsyncInterface,
+        F: FnOnce(&mut W<REG>) -> &mut W<REG>,
+    {
+        let value = f(&mut Self::writer()).bits;
+        bus.write_register(self.addr, REG::SIZE, value.to_u64())
+            .await?;
+        Ok(value)
+    }
+}
+
+impl<REG: Writable + RegisterSize> Reg<REG>
+where
+    REG::Ux: Bits,
+{
+    /// Writes 0 to a `Writable` register.
+    ///
+    /// Similar to `write`, but unused bits will contain 0.
+    ///
+    /// # Safety
+    ///
+    /// Unsafe to use with registers which don't allow to write 0.
+    #[inline(always)]
+    pub unsafe fn write_with_zero<I, F>(&self, bus: &mut I, f: F) -> Result<REG::Ux, I::Error>
+    where
+        I: Interface,
+        F: FnOnce(&mut W<REG>) -> &mut W<REG>,
+    {
+        let value = f(&mut W {
+            bits: REG::Ux::default(),
+            _reg: marker::PhantomData,
+        })
+        .bits;
+        bus.write_register(self.addr, REG::SIZE, value.to_u64())?;
+        Ok(value)
+    }
+}
+
+impl<REG: Readable + Writable + RegisterSize> Reg<REG>
+where
+    REG::Ux: Bits,
+{
+    /// Modifies the contents of the register by reading and then writing it.
+    ///
+    /// ```ignore
+    /// dev.ctrl().modify(&mut bus, |_, w| w.en().clear_bit())?;
+    /// ```
+    /// Other fields will have the value they had before the call to `modify`.
+    #[inline(always)]
+    pub fn modify<I, F>(&self, bus: &mut I, f: F) -> Result<REG::Ux, I::Error>
+    where
+        I: Interface,
+        for<'w> F: FnOnce(&R<REG>, &'w mut W<REG>) -> &'w mut W<REG>,
+    {
+        let bits = REG::Ux::from_u64(bus.read_register(self.addr, REG::SIZE)?);
+        let value = Self::modified(bits, f);
+        bus.write_register(self.addr, REG::SIZE, value.to_u64())?;
+        Ok(value)
+    }
+
+    /// Modifies the contents of the register by reading and then writing it.
+    #[inline(always)]
+    pub async fn modify_async<I, F>(&self, bus: &mut I, f: F) -> Result<REG::Ux, I::Error>
+    where
+        I: AsyncInterface,
+        for<'w> F: FnOnce(&R<REG>, &'w mut W<REG>) -> &'w mut W<REG>,
+    {
+        let bits = REG::Ux::from_u64(bus.read_register(self.addr, REG::SIZE).await?);
+        let value = Self::modified(bits, f);
+        bus.write_register(self.addr, REG::SIZE, value.to_u64())
+            .await?;
+        Ok(value)
+    }
+
+    #[inline(always)]
+    fn modified<F>(bits: REG::Ux, f: F) -> REG::Ux
+    where
+        for<'w> F: FnOnce(&R<REG>, &'w mut W<REG>) -> &'w mut W<REG>,
+    {
+        f(
+            &R {
+                bits,
+                _reg: marker::PhantomData,
+            },
+            &mut W {
+                bits: bits & !REG::ONE_TO_MODIFY_FIELDS_BITMAP | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
+                _reg: marker::PhantomData,
+            },
+        )
+        .bits
+    }
+}
+
+impl<REG: ReadFields + RegisterSize> Reg<REG>
+where
+    REG::Ux: Bits,
+{
+    /// Reads values of all fields of the register.
+    ///
+    /// ```ignore
+    /// let config = dev.ctrl().read_value(&mut bus)?;
+    /// ```
+    #[inline(always)]
+    pub fn read_value<I: Interface>(&self, bus: &mut I) -> Result<REG::Fields, I::Error> {
+        Ok(self.read(bus)?.fields())
+    }
+
+    /// Reads values of all fields of the register.
+    #[inline(always)]
+    pub async fn read_value_async<I: AsyncInterface>(
+        &self,
+        bus: &mut I,
+    ) -> Result<REG::Fields, I::Error> {
+        Ok(self.read_async(bus).await?.fields())
+    }
+}
+
+impl<REG: Resettable + WriteFields<FieldsSafety = Safe> + RegisterSize> Reg<REG>
+where
+    REG::Ux: Bits,
+{
+    /// Writes values of all fields to the register.
+    ///
+    /// ```ignore
+    /// dev.ctrl().write_value(&mut bus, ctrl::Fields { en: true, ..config })?;
+    /// ```
+    /// Other bits will be set to their reset value.
+    #[inline(always)]
+    pub fn write_value<I: Interface>(
+        &self,
+        bus: &mut I,
+        fields: REG::Fields,
+    ) -> Result<REG::Ux, I::Error> {
+        self.write(bus, |w| w.set_fields(fields))
+    }
+
+    /// Writes values of all fields to the register.
+    #[inline(always)]
+    pub async fn write_value_async<I: AsyncInterface>(
+        &self,
+        bus: &mut I,
+        fields: REG::Fields,
+    ) -> Result<REG::Ux, I::Error> {
+        self.write_async(bus, |w| w.set_fields(fields)).await
+    }
+}
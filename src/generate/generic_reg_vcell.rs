@@ -17,6 +17,16 @@ impl<REG: RegisterSpec> Reg<REG> {
     pub fn as_ptr(&self) -> *mut REG::Ux {
         self.register.as_ptr()
     }
+
+    #[inline(always)]
+    fn get(&self) -> REG::Ux {
+        self.register.get().swap_endian(REG::ENDIAN)
+    }
+
+    #[inline(always)]
+    fn set(&self, bits: REG::Ux) {
+        self.register.set(bits.swap_endian(REG::ENDIAN))
+    }
 }
 
 impl<REG: Readable> Reg<REG> {
@@ -35,19 +45,79 @@ impl<REG: Readable> Reg<REG> {
     #[inline(always)]
     pub fn read(&self) -> R<REG> {
         R {
-            bits: self.register.get(),
+            bits: self.get(),
             _reg: marker::PhantomData,
         }
     }
 }
 
+impl<REG: Readable> Reg<REG> {
+    /// Reads the register until `f` returns `true` for the value and returns the value.
+    ///
+    /// ```ignore
+    /// periph.sr().wait_until(|r| r.busy().bit_is_clear());
+    /// ```
+    #[inline(always)]
+    pub fn wait_until<F>(&self, mut f: F) -> R<REG>
+    where
+        F: FnMut(&R<REG>) -> bool,
+    {
+        loop {
+            let r = self.read();
+            if f(&r) {
+                return r;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Reads the register until `f` returns `true` for the value, at most `max_reads` times.
+    ///
+    /// ```ignore
+    /// let r = periph.sr().wait_until_bounded(|r| r.ready().bit_is_set(), 1000)?;
+    /// ```
+    #[inline(always)]
+    pub fn wait_until_bounded<F>(&self, mut f: F, max_reads: usize) -> Result<R<REG>, Timeout>
+    where
+        F: FnMut(&R<REG>) -> bool,
+    {
+        for _ in 0..max_reads {
+            let r = self.read();
+            if f(&r) {
+                return Ok(r);
+            }
+            core::hint::spin_loop();
+        }
+        Err(Timeout)
+    }
+
+    /// Reads the register until `f` returns `true` for the value, yielding to other tasks
+    /// between reads.
+    ///
+    /// ```ignore
+    /// periph.sr().wait_until_async(|r| r.busy().bit_is_clear()).await;
+    /// ```
+    pub async fn wait_until_async<F>(&self, mut f: F) -> R<REG>
+    where
+        F: FnMut(&R<REG>) -> bool,
+    {
+        loop {
+            let r = self.read();
+            if f(&r) {
+                return r;
+            }
+            YieldNow(false).await;
+        }
+    }
+}
+
 impl<REG: Resettable + Writable> Reg<REG> {
     /// Writes the reset value to `Writable` register.
     ///
     /// Resets the register to its initial state.
     #[inline(always)]
     pub fn reset(&self) {
-        self.register.set(REG::RESET_VALUE)
+        self.set(REG::RESET_VALUE)
     }
 
     /// Writes bits to a `Writable` register.
@@ -84,7 +154,7 @@ impl<REG: Resettable + Writable> Reg<REG> {
             _reg: marker::PhantomData,
         })
         .bits;
-        self.register.set(value);
+        self.set(value);
         value
     }
 
@@ -128,7 +198,7 @@ impl<REG: Resettable + Writable> Reg<REG> {
         };
         let result = f(&mut writer);
 
-        self.register.set(writer.bits);
+        self.set(writer.bits);
 
         result
     }
@@ -152,7 +222,7 @@ impl<REG: Writable> Reg<REG> {
             _reg: marker::PhantomData,
         })
         .bits;
-        self.register.set(value);
+        self.set(value);
         value
     }
 
@@ -175,7 +245,7 @@ impl<REG: Writable> Reg<REG> {
 
         let result = f(&mut writer);
 
-        self.register.set(writer.bits);
+        self.set(writer.bits);
 
         result
     }
@@ -212,7 +282,7 @@ impl<REG: Readable + Writable> Reg<REG> {
     where
         for<'w> F: FnOnce(&R<REG>, &'w mut W<REG>) -> &'w mut W<REG>,
     {
-        let bits = self.register.get();
+        let bits = self.get();
         let value = f(
             &R {
                 bits,
@@ -224,7 +294,7 @@ impl<REG: Readable + Writable> Reg<REG> {
             },
         )
         .bits;
-        self.register.set(value);
+        self.set(value);
         value
     }
 
@@ -264,7 +334,7 @@ impl<REG: Readable + Writable> Reg<REG> {
     where
         for<'w> F: FnOnce(&R<REG>, &'w mut W<REG>) -> T,
     {
-        let bits = self.register.get();
+        let bits = self.get();
 
         let mut writer = W {
             bits: bits & !REG::ONE_TO_MODIFY_FIELDS_BITMAP | REG::ZERO_TO_MODIFY_FIELDS_BITMAP,
@@ -279,12 +349,37 @@ impl<REG: Readable + Writable> Reg<REG> {
             &mut writer,
         );
 
-        self.register.set(writer.bits);
+        self.set(writer.bits);
 
         result
     }
 }
 
+impl<REG: ReadFields> Reg<REG> {
+    /// Reads values of all fields of the register.
+    ///
+    /// ```ignore
+    /// let config = periph.cr().read_value();
+    /// ```
+    #[inline(always)]
+    pub fn read_value(&self) -> REG::Fields {
+        self.read().fields()
+    }
+}
+
+impl<REG: Resettable + WriteFields<FieldsSafety = Safe>> Reg<REG> {
+    /// Writes values of all fields to the register.
+    ///
+    /// ```ignore
+    /// periph.cr().write_value(cr::Fields { en: true, ..config });
+    /// ```
+    /// Other bits will be set to their reset value.
+    #[inline(always)]
+    pub fn write_value(&self, fields: REG::Fields) -> REG::Ux {
+        self.write(|w| w.set_fields(fields))
+    }
+}
+
 impl<REG: Readable> core::fmt::Debug for crate::generic::Reg<REG>
 where
     R<REG>: core::fmt::Debug,
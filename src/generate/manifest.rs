@@ -0,0 +1,224 @@
+//! `Cargo.toml` of the generated crate
+
+use crate::svd::Device;
+use std::fmt::Write;
+
+use crate::config::{AtomicsBackend, Backend, Config, Target};
+use crate::util;
+use anyhow::Result;
+
+/// Renders `[features]` entries for peripheral groups and peripherals
+pub fn features(d: &Device, config: &Config) -> Vec<String> {
+    let feature_format = config.ident_formats.get("peripheral_feature").unwrap();
+    let mut features = Vec::new();
+    if config.feature_group {
+        let groups = util::group_names(d, feature_format);
+        features.extend(groups.iter().map(|s| format!("{s} = []\n")));
+        let add_groups: Vec<_> = groups.iter().map(|s| format!("\"{s}\"")).collect();
+        features.push(format!("all-groups = [{}]\n", add_groups.join(",")))
+    }
+    if config.feature_peripheral {
+        let peripherals = util::peripheral_names(d, feature_format);
+        features.extend(peripherals.iter().map(|s| format!("{s} = []\n")));
+        let add_peripherals: Vec<_> = peripherals.iter().map(|s| format!("\"{s}\"")).collect();
+        features.push(format!(
+            "all-peripherals = [{}]\n",
+            add_peripherals.join(",")
+        ))
+    }
+    features
+}
+
+/// Renders `Cargo.toml` of the generated crate
+pub fn render(d: &Device, config: &Config) -> Result<String> {
+    let mut out = String::new();
+
+    let name = config
+        .crate_name
+        .clone()
+        .unwrap_or_else(|| crate_name(&d.name));
+    writeln!(out, "[package]")?;
+    writeln!(out, "name = {}", toml_str(&name))?;
+    writeln!(out, "version = \"0.1.0\"")?;
+    writeln!(out, "edition = \"2021\"")?;
+    writeln!(out, "rust-version = \"1.76\"")?;
+    let description = format!(
+        "Peripheral access API for {} microcontrollers (generated using svd2rust v{})",
+        d.name.to_uppercase(),
+        env!("CARGO_PKG_VERSION"),
+    );
+    writeln!(out, "description = {}", toml_str(&description))?;
+    writeln!(
+        out,
+        "categories = [\"embedded\", \"hardware-support\", \"no-std\"]"
+    )?;
+
+    writeln!(out, "\n[package.metadata.svd]")?;
+    writeln!(out, "name = {}", toml_str(&d.name))?;
+    if let Some(vendor) = d.vendor.as_ref() {
+        writeln!(out, "vendor = {}", toml_str(vendor))?;
+    }
+    writeln!(out, "version = {}", toml_str(&d.version))?;
+    if let Some(license) = d.license_text.as_ref() {
+        writeln!(out, "license = {}", toml_str(license.trim()))?;
+    }
+
+    let mut dependencies = vec![(
+        "critical-section",
+        "{ version = \"1.0\", optional = true }".to_string(),
+    )];
+    let mut features = Vec::new();
+    match config.target {
+        Target::CortexM => {
+            dependencies.push(("cortex-m", "\"0.7.6\"".into()));
+            dependencies.push((
+                "cortex-m-rt",
+                "{ version = \"0.7.3\", optional = true }".into(),
+            ));
+            features.push("rt = [\"cortex-m-rt/device\"]".to_string());
+        }
+        Target::Msp430 => {
+            dependencies.push(("msp430", "\"0.4.0\"".into()));
+            dependencies.push((
+                "msp430-rt",
+                "{ version = \"0.4.0\", optional = true }".into(),
+            ));
+            features.push("rt = [\"msp430-rt/device\"]".into());
+        }
+        Target::RISCV => {
+            dependencies.push(("riscv", "\"0.12.1\"".into()));
+            if config
+                .settings
+                .riscv_config
+                .as_ref()
+                .is_some_and(|c| c.clint.is_some() || c.plic.is_some())
+            {
+                dependencies.push(("riscv-peripheral", "\"0.2.0\"".into()));
+            }
+            dependencies.push((
+                "riscv-rt",
+                "{ version = \"0.13.0\", optional = true }".into(),
+            ));
+            features.push("rt = [\"riscv-rt\"]".into());
+            features.push("v-trap = [\"rt\", \"riscv-rt/v-trap\"]".into());
+        }
+        Target::XtensaLX => {
+            dependencies.push(("xtensa-lx", "\"0.9.0\"".into()));
+            dependencies.push((
+                "xtensa-lx-rt",
+                "{ version = \"0.17.0\", optional = true }".into(),
+            ));
+            features.push("rt = [\"xtensa-lx-rt\"]".into());
+        }
+        Target::Mips => {
+            dependencies.push(("mips-mcu", "\"0.3.0\"".into()));
+            dependencies.push(("mips-rt", "{ version = \"0.3.0\", optional = true }".into()));
+            features.push("rt = [\"mips-rt\"]".into());
+        }
+        Target::None => {
+            features.push("rt = []".into());
+        }
+    }
+    if config.backend == Backend::Vcell {
+        dependencies.push(("vcell", "\"0.1.2\"".into()));
+    }
+    if config.atomics && config.backend.has_atomics() {
+        let feature = config.atomics_feature.as_deref().map(toml_key);
+        match config.atomics_backend {
+            AtomicsBackend::Portable => {
+                if let Some(feature) = feature {
+                    dependencies.push((
+                        "portable-atomic",
+                        "{ version = \"0.3.16\", default-features = false, optional = true }"
+                            .into(),
+                    ));
+                    features.push(format!("{feature} = [\"dep:portable-atomic\"]"));
+                } else {
+                    dependencies.push((
+                        "portable-atomic",
+                        "{ version = \"0.3.16\", default-features = false }".into(),
+                    ));
+                }
+            }
+            AtomicsBackend::Native => {
+                if let Some(feature) = feature {
+                    features.push(format!("{feature} = []"));
+                }
+            }
+            AtomicsBackend::CriticalSection | AtomicsBackend::Auto => {
+                // `critical-section` is optional for `Peripherals::take`, atomics need it
+                if let Some(feature) = feature {
+                    features.push(format!("{feature} = [\"critical-section\"]"));
+                } else {
+                    dependencies[0].1 = "\"1.0\"".into();
+                    features.push("critical-section = []".into());
+                }
+            }
+        }
+    }
+    if let Some(feature) = config.impl_defmt.as_ref() {
+        dependencies.push(("defmt", "{ version = \"0.3.5\", optional = true }".into()));
+        features.push(format!("{} = [\"dep:defmt\"]", toml_key(feature)));
+    }
+    if let Some(feature) = config.impl_debug_feature.as_ref() {
+        features.push(format!("{} = []", toml_key(feature)));
+    }
+
+    writeln!(out, "\n[dependencies]")?;
+    for (name, version) in dependencies {
+        writeln!(out, "{name} = {version}")?;
+    }
+
+    writeln!(out, "\n[features]")?;
+    for feature in features {
+        writeln!(out, "{feature}")?;
+    }
+    for feature in self::features(d, config) {
+        out.push_str(&feature);
+    }
+
+    Ok(out)
+}
+
+/// Makes a valid package name from the device name
+fn crate_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn toml_key(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        s.into()
+    } else {
+        toml_str(s)
+    }
+}
+
+fn toml_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
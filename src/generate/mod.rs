@@ -1,5 +1,6 @@
 pub mod device;
 pub mod interrupt;
+pub mod manifest;
 pub mod peripheral;
 pub mod register;
 pub mod riscv;
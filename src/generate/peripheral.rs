@@ -7,7 +7,8 @@ use svd_parser::expand::{
 };
 use syn::LitInt;
 
-use crate::config::Config;
+use crate::config::{Backend, Config, Endian, Target};
+use crate::diagnostic::{ElementContext, ElementKind};
 use crate::svd::{
     self, Cluster, ClusterInfo, MaybeArray, Peripheral, Register, RegisterCluster, RegisterInfo,
 };
@@ -37,6 +38,25 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
         path = derive_peripheral(&mut p, &dpath, index)?;
     }
 
+    // bit-band alias writers only for little-endian peripherals in the bit-band region
+    let bitband_config;
+    let config = if config.bitband
+        && (config.target != Target::CortexM
+            || !config.backend.has_atomics()
+            || config.settings.endian(&p.name) == Some(Endian::Big)
+            || !util::in_bitband_region(
+                p.base_address + config.base_address_shift,
+                peripheral_size(&p, config)?,
+            )) {
+        bitband_config = Config {
+            bitband: false,
+            ..config.clone()
+        };
+        &bitband_config
+    } else {
+        config
+    };
+
     let name = util::name_of(&p, config.ignore_groups);
     let span = Span::call_site();
     let p_ty = ident(&name, config, "peripheral", span);
@@ -62,6 +82,39 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
         feature_attribute.extend(quote! { #[cfg(feature = #feature_name)] });
     };
 
+    // with block handles the peripheral holds its register block, which is a pointer or an address
+    let handles = config.backend.has_block_handles();
+    let (ptr_ty, arg, from, as_, base_const) = match config.backend {
+        Backend::Transport => (
+            quote!(u64),
+            quote!(addr),
+            quote!(from_addr),
+            quote!(addr),
+            quote!(ADDRESS),
+        ),
+        Backend::Ptr => (
+            quote!(*mut u8),
+            quote!(ptr),
+            quote!(from_ptr),
+            quote!(as_ptr),
+            quote!(PTR),
+        ),
+        _ => (
+            quote!(*const #base::RegisterBlock),
+            quote!(ptr),
+            quote!(from_ptr),
+            quote!(as_ptr),
+            quote!(PTR),
+        ),
+    };
+    let marker_init = if handles {
+        quote!(block: #base::RegisterBlock::#from(Self::#base_const))
+    } else if config.relocatable {
+        quote!(ptr: Self::PTR)
+    } else {
+        quote!(_marker: PhantomData)
+    };
+
     let steal_fn = quote! {
         /// Steal an instance of this peripheral
         ///
@@ -77,7 +130,7 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
         /// peripheral instance existing to ensure memory safety; ensure
         /// no stolen instances are passed to such software.
         pub unsafe fn steal() -> Self {
-            Self { _marker: PhantomData }
+            Self { #marker_init }
         }
     };
 
@@ -92,28 +145,106 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
                          p_ty: &Ident,
                          doc_alias: Option<TokenStream>,
                          address: LitInt| {
+        let ptr_fns = config.relocatable.then(|| {
+            let (from_body, as_body) = if handles {
+                (
+                    quote!(Self { block: #base::RegisterBlock::#from(#arg) }),
+                    quote!(self.block.#as_()),
+                )
+            } else {
+                (quote!(Self { ptr }), quote!(self.ptr))
+            };
+            let (from_doc, safety_doc, as_doc) = if config.backend == Backend::Transport {
+                (
+                    "Create an instance of this peripheral with the register block at bus address `addr`",
+                    "`addr` must be the address of a register block of this peripheral on the bus.
+The same requirements as for [`Self::steal`] apply to other instances with the same address.",
+                    "Return the bus address of the register block of this instance",
+                )
+            } else {
+                (
+                    "Create an instance of this peripheral with the register block at `ptr`",
+                    "`ptr` must point to a register block of this peripheral that stays mapped while
+the instance and its copies are used. The same requirements as for [`Self::steal`] apply to
+other instances with the same pointer.",
+                    "Return the pointer to the register block of this instance",
+                )
+            };
+            quote! {
+                #[doc = #from_doc]
+                ///
+                /// # Safety
+                ///
+                #[doc = #safety_doc]
+                #[inline(always)]
+                pub const unsafe fn #from(#arg: #ptr_ty) -> Self {
+                    #from_body
+                }
+
+                #[doc = #as_doc]
+                #[inline(always)]
+                pub const fn #as_(&self) -> #ptr_ty {
+                    #as_body
+                }
+            }
+        });
+        let (fields, deref) = if handles {
+            (quote!(block: #base::RegisterBlock), quote!(&self.block))
+        } else if config.backend == Backend::Sim {
+            // the addresses of peripherals are not mapped on the host
+            let ptr = if config.relocatable {
+                quote!(self.ptr)
+            } else {
+                quote!(Self::PTR)
+            };
+            let field = if config.relocatable {
+                quote!(ptr: #ptr_ty)
+            } else {
+                quote!(_marker: PhantomData<*const ()>)
+            };
+            (field, quote!(crate::generic::sim::block(#ptr as usize)))
+        } else if config.relocatable {
+            (quote!(ptr: #ptr_ty), quote!(unsafe { &*self.ptr }))
+        } else {
+            (
+                quote!(_marker: PhantomData<*const ()>),
+                quote!(unsafe { &*Self::PTR }),
+            )
+        };
+        let base_fns = if config.backend == Backend::Transport {
+            quote! {
+                ///Address of the register block on the bus
+                pub const ADDRESS: u64 = #address;
+            }
+        } else {
+            quote! {
+                ///Pointer to the register block
+                pub const PTR: #ptr_ty = #address as #ptr_ty;
+
+                ///Return the pointer to the register block
+                #[inline(always)]
+                pub const fn ptr() -> #ptr_ty {
+                    Self::PTR
+                }
+            }
+        };
         out.extend(quote! {
             #[doc = #description]
             #phtml
             #doc_alias
             #feature_attribute
-            pub struct #p_ty { _marker: PhantomData<*const ()> }
+            pub struct #p_ty { #fields }
 
             #feature_attribute
             unsafe impl Send for #p_ty {}
 
             #feature_attribute
             impl #p_ty {
-                ///Pointer to the register block
-                pub const PTR: *const #base::RegisterBlock = #address as *const _;
-
-                ///Return the pointer to the register block
-                #[inline(always)]
-                pub const fn ptr() -> *const #base::RegisterBlock {
-                    Self::PTR
-                }
+                #base_fns
 
                 #steal_fn
+
+                #ptr_fns
             }
 
             #feature_attribute
@@ -122,7 +253,7 @@ pub fn render(p_original: &Peripheral, index: &Index, config: &Config) -> Result
 
                 #[inline(always)]
                 fn deref(&self) -> &Self::Target {
-                    unsafe { &*Self::PTR }
+                    #deref
                 }
             }
 
@@ -535,6 +666,28 @@ fn register_or_cluster_block(
     let ercs_expanded = expand(ercs, derive_infos, config)
         .with_context(|| "Could not expand register or cluster block")?;
 
+    let span = Span::call_site();
+    let mut doc_alias = None;
+    let block_ty = if let Some(name) = name {
+        let ty = ident(name, config, "cluster", span);
+        if ty != name {
+            doc_alias = Some(quote!(#[doc(alias = #name)]));
+        }
+        ty
+    } else {
+        Ident::new("RegisterBlock", span)
+    };
+
+    if config.backend.has_block_handles() {
+        return Ok(block_handle(
+            &ercs_expanded,
+            &block_ty,
+            doc,
+            doc_alias,
+            config,
+        ));
+    }
+
     // Locate conflicting regions; we'll need to use unions to represent them.
     let mut regions = FieldRegions::default();
 
@@ -547,7 +700,6 @@ fn register_or_cluster_block(
     // The end of the region for which we previously emitted a rbf into `rbfs`
     let mut last_end = 0;
 
-    let span = Span::call_site();
     for (i, region) in regions.regions.iter().enumerate() {
         // Check if we need padding
         let pad = region.offset - last_end;
@@ -630,17 +782,6 @@ fn register_or_cluster_block(
         }
     });
 
-    let mut doc_alias = None;
-    let block_ty = if let Some(name) = name {
-        let ty = ident(name, config, "cluster", span);
-        if ty != name {
-            doc_alias = Some(quote!(#[doc(alias = #name)]));
-        }
-        ty
-    } else {
-        Ident::new("RegisterBlock", span)
-    };
-
     let accessors = (!accessors.is_empty()).then(|| {
         quote! {
             impl #block_ty {
@@ -662,6 +803,74 @@ fn register_or_cluster_block(
     })
 }
 
+/// Register block or cluster as a handle with the pointer or the address of the block
+fn block_handle(
+    ercs_expanded: &[RegisterBlockField],
+    block_ty: &Ident,
+    doc: &str,
+    doc_alias: Option<TokenStream>,
+    config: &Config,
+) -> TokenStream {
+    let mut accessors = TokenStream::new();
+    for a in ercs_expanded.iter().flat_map(|rbf| &rbf.accessors) {
+        match config.backend {
+            Backend::Transport => a.clone().addr(),
+            _ => a.clone().ptr(),
+        }
+        .to_tokens(&mut accessors);
+    }
+
+    let (field, field_ty, from, as_) = match config.backend {
+        Backend::Transport => (quote!(addr), quote!(u64), quote!(from_addr), quote!(addr)),
+        _ => (
+            quote!(ptr),
+            quote!(*mut u8),
+            quote!(from_ptr),
+            quote!(as_ptr),
+        ),
+    };
+    let (from_doc, safety_doc, as_doc) = match config.backend {
+        Backend::Transport => (
+            "Creates the block at bus address `addr`",
+            "`addr` must be the address of a block of this type on the bus.",
+            "Returns the bus address of the block",
+        ),
+        _ => (
+            "Creates the block at `ptr`",
+            "`ptr` must point to a block of this type that stays mapped while the block and its copies are used.",
+            "Returns the pointer to the block",
+        ),
+    };
+    quote! {
+        #[doc = #doc]
+        #doc_alias
+        #[derive(Clone, Copy)]
+        pub struct #block_ty {
+            #field: #field_ty,
+        }
+
+        impl #block_ty {
+            #[doc = #from_doc]
+            ///
+            /// # Safety
+            ///
+            #[doc = #safety_doc]
+            #[inline(always)]
+            pub const unsafe fn #from(#field: #field_ty) -> Self {
+                Self { #field }
+            }
+
+            #[doc = #as_doc]
+            #[inline(always)]
+            pub const fn #as_(&self) -> #field_ty {
+                self.#field
+            }
+
+            #accessors
+        }
+    }
+}
+
 /// Expand a list of parsed `Register`s or `Cluster`s, and render them to
 /// `RegisterBlockField`s containing `Field`s.
 fn expand(
@@ -945,12 +1154,33 @@ fn cluster_size_in_bits(cluster: &Cluster, config: &Config) -> Result<u32> {
     }
 }
 
+/// Size in bytes of the address blocks and registers of a peripheral, or of all the elements
+/// of a peripheral array
+fn peripheral_size(p: &Peripheral, config: &Config) -> Result<u64> {
+    let blocks = p.address_block.iter().flatten();
+    let blocks_end = blocks.map(|b| u64::from(b.offset) + u64::from(b.size));
+    let registers = p.registers.as_deref().unwrap_or(&[]);
+    let registers_end = registers_size_in_bits(registers, config)?.div_ceil(BITS_PER_BYTE);
+    let size = blocks_end.fold(u64::from(registers_end), u64::max);
+    Ok(match p {
+        Peripheral::Single(_) => size,
+        Peripheral::Array(_, dim) => {
+            u64::from(dim.dim.saturating_sub(1)) * u64::from(dim.dim_increment) + size
+        }
+    })
+}
+
 /// Recursively calculate the size of a ClusterInfo. A cluster's size is the
 /// maximum end position of its recursive children.
 fn cluster_info_size_in_bits(info: &ClusterInfo, config: &Config) -> Result<u32> {
+    registers_size_in_bits(&info.children, config)
+}
+
+/// Maximum end position of registers and clusters
+fn registers_size_in_bits(children: &[RegisterCluster], config: &Config) -> Result<u32> {
     let mut size = 0;
 
-    for c in &info.children {
+    for c in children {
         let end = match c {
             RegisterCluster::Register(reg) => {
                 let reg_size: u32 = expand_register(reg, &DeriveInfo::Root, config)?
@@ -1119,7 +1349,7 @@ fn expand_cluster(cluster: &Cluster, config: &Config) -> Result<Vec<RegisterBloc
                         doc,
                         name,
                         ty: ty.clone(),
-                        offset: info.address_offset,
+                        offset: ci.address_offset,
                     })
                     .raw_if(false);
                     cluster_expanded.push(RegisterBlockField {
@@ -1301,7 +1531,7 @@ fn expand_register(
                 for ri in svd::register::expand(info, array_info) {
                     let doc = make_comment(
                         register_size,
-                        info.address_offset,
+                        ri.address_offset,
                         ri.description.as_deref().unwrap_or(&ri.name),
                     );
                     let name = ident(&ri.name, config, "register_accessor", span);
@@ -1311,7 +1541,7 @@ fn expand_register(
                         doc,
                         name,
                         ty: ty.clone(),
-                        offset: info.address_offset,
+                        offset: ri.address_offset,
                     })
                     .raw_if(false);
                     register_expanded.push(RegisterBlockField {
@@ -1348,7 +1578,14 @@ fn render_ercs(
                 if let Some(dpath) = dpath {
                     cpath = derive_cluster(c, &dpath, path, index)?;
                 }
-                mod_items.extend(cluster_block(c, path, cpath, index, config)?);
+                let cluster_path = path.new_cluster(&c.name);
+                mod_items.extend(
+                    cluster_block(c, path, cpath, index, config).element_context(
+                        ElementKind::Cluster,
+                        &cluster_path,
+                        || format!("can't render cluster '{}'", c.name),
+                    )?,
+                );
             }
 
             // Generate definition for each of the registers.
@@ -1365,7 +1602,9 @@ fn render_ercs(
                 }
                 let reg_name = &reg.name;
                 let rendered_reg = register::render(reg, path, rpath, index, config)
-                    .with_context(|| format!("can't render register '{reg_name}'"))?;
+                    .element_context(ElementKind::Register, path.new_register(reg_name), || {
+                        format!("can't render register '{reg_name}'")
+                    })?;
                 mod_items.extend(rendered_reg)
             }
         }
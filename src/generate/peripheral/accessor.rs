@@ -14,6 +14,10 @@ pub enum Accessor {
 pub enum AccessType {
     Ref(Accessor),
     RawRef(Accessor),
+    /// Register or cluster created from the pointer of the block
+    Ptr(Accessor),
+    /// Register or cluster created from the address of the block
+    Addr(Accessor),
 }
 
 impl Accessor {
@@ -29,10 +33,24 @@ impl Accessor {
 impl AccessType {
     pub fn raw(self) -> Self {
         match self {
-            Self::RawRef(_) => self,
             Self::Ref(a) => Self::RawRef(a),
+            _ => self,
         }
     }
+
+    fn accessor(self) -> Accessor {
+        match self {
+            Self::Ref(a) | Self::RawRef(a) | Self::Ptr(a) | Self::Addr(a) => a,
+        }
+    }
+
+    pub fn ptr(self) -> Self {
+        Self::Ptr(self.accessor())
+    }
+
+    pub fn addr(self) -> Self {
+        Self::Addr(self.accessor())
+    }
 }
 
 impl ToTokens for AccessType {
@@ -118,6 +136,8 @@ impl ToTokens for AccessType {
                     }
                 }
             }
+            Self::Ptr(a) => handle_accessor(a, false),
+            Self::Addr(a) => handle_accessor(a, true),
             Self::RawRef(Accessor::ArrayElem(elem)) | Self::Ref(Accessor::ArrayElem(elem)) => {
                 let ArrayElemAccessor {
                     doc,
@@ -140,6 +160,104 @@ impl ToTokens for AccessType {
     }
 }
 
+/// Accessor of a block handle, which creates the register or cluster from the pointer
+/// (`addr == false`) or the address of the block
+fn handle_accessor(accessor: &Accessor, addr: bool) -> TokenStream {
+    // `base` is the address of the block, `n` is the index in array
+    let at = |base: TokenStream, offset: u32, increment: Option<u32>| {
+        let offset = (offset != 0).then(|| unsuffixed(offset));
+        let increment = increment.map(|i| {
+            let i = (i != 1).then(|| {
+                let i = unsuffixed(i);
+                quote!(#i *)
+            });
+            if addr {
+                quote!(+ #i n as u64)
+            } else {
+                quote!(.add(#i n))
+            }
+        });
+        if addr {
+            let offset = offset.map(|o| quote!(+ #o));
+            quote!(from_addr(#base #offset #increment))
+        } else {
+            let offset = offset.map(|o| quote!(.add(#o)));
+            quote!(from_ptr(#base #offset #increment))
+        }
+    };
+    let field = if addr { quote!(addr) } else { quote!(ptr) };
+    match accessor {
+        Accessor::Reg(RegAccessor {
+            doc,
+            name,
+            ty,
+            offset,
+        }) => {
+            let at = at(quote!(self.#field), *offset, None);
+            quote! {
+                #[doc = #doc]
+                #[inline(always)]
+                pub const fn #name(&self) -> #ty {
+                    unsafe { <#ty>::#at }
+                }
+            }
+        }
+        Accessor::Array(ArrayAccessor {
+            doc,
+            name,
+            ty,
+            offset,
+            dim,
+            increment,
+            note,
+        }) => {
+            let name_iter = Ident::new(&format!("{name}_iter"), Span::call_site());
+            let at_self = at(quote!(self.#field), *offset, Some(*increment));
+            let at_base = at(quote!(#field), *offset, Some(*increment));
+            let dim = unsuffixed(*dim);
+            let note = note.as_ref().map(|note| {
+                quote! {
+                    #[doc = ""]
+                    #[doc = #note]
+                }
+            });
+            quote! {
+                #[doc = #doc]
+                #note
+                #[inline(always)]
+                pub const fn #name(&self, n: usize) -> #ty {
+                    #[allow(clippy::no_effect)]
+                    [(); #dim][n];
+                    unsafe { <#ty>::#at_self }
+                }
+                #[doc = "Iterator for array of:"]
+                #[doc = #doc]
+                #[inline(always)]
+                pub fn #name_iter(&self) -> impl Iterator<Item=#ty> {
+                    let #field = self.#field;
+                    (0..#dim).map(move |n| unsafe { <#ty>::#at_base })
+                }
+            }
+        }
+        Accessor::ArrayElem(ArrayElemAccessor {
+            doc,
+            name,
+            ty,
+            basename,
+            i,
+        }) => {
+            let i = unsuffixed(*i as u64);
+            quote! {
+                #[doc = #doc]
+                #[inline(always)]
+                pub const fn #name(&self) -> #ty {
+                    self.#basename(#i)
+                }
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct RegAccessor {
     pub doc: String,
@@ -13,11 +13,12 @@ use svd_parser::expand::{
     derive_enumerated_values, derive_field, BlockPath, EnumPath, FieldPath, Index, RegisterPath,
 };
 
-use crate::config::Config;
+use crate::config::{Backend, Config};
+use crate::diagnostic::{ElementContext, ElementKind};
 use crate::util::{
     self, ident, ident_to_path, path_segment, type_path, unsuffixed, DimSuffix, FullName, U32Ext,
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use syn::punctuated::Punctuated;
 
 fn regspec(name: &str, config: &Config, span: Span) -> Ident {
@@ -26,13 +27,20 @@ fn regspec(name: &str, config: &Config, span: Span) -> Ident {
 
 fn field_accessor(name: &str, config: &Config, span: Span) -> Ident {
     const INTERNALS: [&str; 2] = ["bits", "set"];
+    // methods of registers with plain structs of field values
+    const FIELD_STRUCTS: [&str; 2] = ["fields", "set_fields"];
+    // methods of registers with values built in constant context
+    const CONST_VALUES: [&str; 2] = ["from_bits", "set_value"];
     let sc = config
         .ident_formats
         .get("field_accessor")
         .unwrap()
         .sanitize(name);
     Ident::new(
-        &(if INTERNALS.contains(&sc.as_ref()) {
+        &(if INTERNALS.contains(&sc.as_ref())
+            || (config.field_structs && FIELD_STRUCTS.contains(&sc.as_ref()))
+            || (config.const_values && CONST_VALUES.contains(&sc.as_ref()))
+        {
             sc + "_"
         } else {
             sc
@@ -290,8 +298,10 @@ pub fn render_register_mod(
     let mut r_impl_items = TokenStream::new();
     let mut r_debug_impl = TokenStream::new();
     let mut w_impl_items = TokenStream::new();
-    let mut zero_to_modify_fields_bitmap = 0;
-    let mut one_to_modify_fields_bitmap = 0;
+    let mut bitband_items = TokenStream::new();
+    let mut field_values = Vec::new();
+    let mut value_items = TokenStream::new();
+    let mut modify_bitmaps = ModifyBitmaps::default();
 
     let debug_feature = config
         .impl_debug_feature
@@ -316,22 +326,28 @@ pub fn render_register_mod(
                 ))
             }
 
-            (
-                r_impl_items,
-                w_impl_items,
-                zero_to_modify_fields_bitmap,
-                one_to_modify_fields_bitmap,
-            ) = fields(
+            // the bus reads the whole register and writes it back on bit-band writes
+            let bitband = register.read_action.is_none()
+                && cur_fields.iter().all(|f| f.read_action.is_none());
+            (r_impl_items, w_impl_items, bitband_items, modify_bitmaps) = fields(
                 cur_fields,
                 &regspec_ty,
                 register.modified_write_values,
                 access,
                 properties,
                 &mut mod_items,
+                &mut field_values,
+                &mut value_items,
                 rpath,
                 index,
                 config,
             )?;
+            if !bitband
+                || modify_bitmaps.zero_to_modify() != 0
+                || modify_bitmaps.one_to_modify() != 0
+            {
+                bitband_items = TokenStream::new();
+            }
         }
     } else if !access.can_read() || register.read_action.is_some() {
         r_debug_impl.extend(quote! {
@@ -366,6 +382,38 @@ pub fn render_register_mod(
             impl W { #w_impl_items }
         });
     }
+    if !bitband_items.is_empty() {
+        mod_items.extend(quote! {
+            impl crate::generic::Reg<#regspec_ty> { #bitband_items }
+        });
+    }
+    if !field_values.is_empty() {
+        mod_items.extend(render_field_values(
+            &field_values,
+            &regspec_ty,
+            rname,
+            access,
+        ));
+    }
+
+    if config.const_values && can_write {
+        mod_items.extend(quote! {
+            impl crate::WriteValue for #regspec_ty {}
+        });
+        if let Some(rv) = properties.reset_value {
+            let rv =
+                util::hex(rv & !modify_bitmaps.one_to_modify() | modify_bitmaps.zero_to_modify());
+            mod_items.extend(quote! {
+                #[doc = "Initial value of the writer, used to build register values in constant context"]
+                pub const RESET: crate::Value<#regspec_ty> = unsafe { crate::Value::from_bits(#rv) };
+            });
+        }
+        if !value_items.is_empty() {
+            mod_items.extend(quote! {
+                impl crate::Value<#regspec_ty> { #value_items }
+            });
+        }
+    }
 
     let doc = format!(
         "{description}{}{}",
@@ -373,12 +421,20 @@ pub fn render_register_mod(
         read_action_docs(access.can_read(), register.read_action),
     );
 
+    let endian = match config.settings.endian(&rpath.block.peripheral) {
+        Some(endian) => {
+            let endian = Ident::new(&format!("{endian:?}"), span);
+            quote!(const ENDIAN: crate::Endian = crate::Endian::#endian;)
+        }
+        None => quote!(),
+    };
     mod_items.extend(quote! {
         #[doc = #doc]
         pub struct #regspec_ty;
 
         impl crate::RegisterSpec for #regspec_ty {
             type Ux = #rty;
+            #endian
         }
     });
 
@@ -413,8 +469,31 @@ pub fn render_register_mod(
 
         let doc = format!("`write(|w| ..)` method takes [`{mod_ty}::W`](W) writer structure",);
 
-        let zero_to_modify_fields_bitmap = util::hex(zero_to_modify_fields_bitmap);
-        let one_to_modify_fields_bitmap = util::hex(one_to_modify_fields_bitmap);
+        let zero_to_modify_fields_bitmap = util::hex(modify_bitmaps.zero_to_modify());
+        let one_to_modify_fields_bitmap = util::hex(modify_bitmaps.one_to_modify());
+
+        let mut alias_consts = TokenStream::new();
+        if config.atomics && can_read {
+            let [set, clear, toggle] = aliases(register, rpath, index, config)?;
+            for (name, offset) in [
+                ("SET_ALIAS", set),
+                ("CLEAR_ALIAS", clear),
+                ("TOGGLE_ALIAS", toggle),
+            ] {
+                if let Some(offset) = offset {
+                    let name = Ident::new(name, span);
+                    let value = util::hex(offset.unsigned_abs());
+                    let value = if offset < 0 {
+                        quote!(-#value)
+                    } else {
+                        quote!(#value)
+                    };
+                    alias_consts.extend(quote! {
+                        const #name: Option<isize> = Some(#value);
+                    });
+                }
+            }
+        }
 
         mod_items.extend(quote! {
             #[doc = #doc]
@@ -422,6 +501,7 @@ pub fn render_register_mod(
                 type Safety = crate::#safe_ty;
                 const ZERO_TO_MODIFY_FIELDS_BITMAP: #rty = #zero_to_modify_fields_bitmap;
                 const ONE_TO_MODIFY_FIELDS_BITMAP: #rty = #one_to_modify_fields_bitmap;
+                #alias_consts
             }
         });
     }
@@ -434,9 +514,134 @@ pub fn render_register_mod(
             }
         });
     }
+    if config.backend == Backend::Transport {
+        let size = util::unsuffixed(u64::from(properties.size.unwrap_or(rsize)));
+        mod_items.extend(quote! {
+            impl crate::generic::transport::RegisterSize for #regspec_ty {
+                const SIZE: u32 = #size;
+            }
+        });
+    }
+    if config.backend == Backend::Sim {
+        let initial = util::hex(properties.reset_value.unwrap_or(0));
+        let mut bitmaps = TokenStream::new();
+        for (name, bitmap) in [
+            ("ONE_TO_SET", modify_bitmaps.one_to_set),
+            ("ONE_TO_CLEAR", modify_bitmaps.one_to_clear),
+            ("ONE_TO_TOGGLE", modify_bitmaps.one_to_toggle),
+            ("ZERO_TO_SET", modify_bitmaps.zero_to_set),
+            ("ZERO_TO_CLEAR", modify_bitmaps.zero_to_clear),
+            ("ZERO_TO_TOGGLE", modify_bitmaps.zero_to_toggle),
+            ("WRITE_SET", modify_bitmaps.set),
+            ("WRITE_CLEAR", modify_bitmaps.clear),
+        ] {
+            if bitmap != 0 {
+                let name = Ident::new(name, span);
+                let bitmap = util::hex(bitmap);
+                bitmaps.extend(quote!(const #name: u64 = #bitmap;));
+            }
+        }
+        mod_items.extend(quote! {
+            impl crate::generic::sim::Simulated for #regspec_ty {
+                const INITIAL_VALUE: #rty = #initial;
+                #bitmaps
+            }
+        });
+    }
     Ok(mod_items)
 }
 
+/// Struct of field values with conversions from the reader and to the writer
+fn render_field_values(
+    field_values: &[FieldValue],
+    regspec_ty: &Ident,
+    rname: &str,
+    access: Access,
+) -> TokenStream {
+    let names: Vec<_> = field_values.iter().map(|v| &v.name).collect();
+    let tys = field_values.iter().map(|v| &v.ty);
+    let docs = field_values.iter().map(|v| &v.doc);
+    let doc = format!("Values of fields of register `{rname}`");
+    let mut out = quote! {
+        #[doc = #doc]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct Fields {
+            #(
+                #[doc = #docs]
+                pub #names: #tys,
+            )*
+        }
+
+        impl crate::FieldValues for #regspec_ty {
+            type Fields = Fields;
+        }
+    };
+    if access.can_read() {
+        let reads = field_values.iter().map(|v| &v.read);
+        out.extend(quote! {
+            impl crate::ReadFields for #regspec_ty {
+                #[inline(always)]
+                fn unpack(r: &R) -> Fields {
+                    Fields {
+                        #(#names: #reads,)*
+                    }
+                }
+            }
+        });
+    }
+    let writes: Vec<_> = field_values
+        .iter()
+        .filter_map(|v| v.write.as_ref())
+        .collect();
+    if access.can_write() && !writes.is_empty() {
+        let safety = if field_values.iter().all(|v| v.safe) {
+            quote!(Safe)
+        } else {
+            quote!(Unsafe)
+        };
+        out.extend(quote! {
+            impl crate::WriteFields for #regspec_ty {
+                type FieldsSafety = crate::#safety;
+                #[inline(always)]
+                unsafe fn pack(fields: Fields, w: &mut W) -> &mut W {
+                    w #(#writes)*
+                }
+            }
+        });
+    }
+    out
+}
+
+/// Offsets of the hardware set, clear and toggle aliases of the register from settings
+fn aliases(
+    register: &Register,
+    rpath: &RegisterPath,
+    index: &Index,
+    config: &Config,
+) -> Result<[Option<i64>; 3]> {
+    let aliases = config.settings.peripheral_aliases(&rpath.block.peripheral);
+    let find = |offset: Option<u64>, name: Option<&String>| -> Result<Option<i64>> {
+        Ok(match (offset, name) {
+            (Some(_), Some(_)) => {
+                bail!("Alias can't have both offset and name")
+            }
+            (Some(offset), None) => Some(offset as i64),
+            (None, Some(name)) => {
+                let apath = rpath.block.new_register(name.replace("{}", &register.name));
+                index.registers.get(&apath).map(|alias| {
+                    i64::from(alias.address_offset) - i64::from(register.address_offset)
+                })
+            }
+            (None, None) => None,
+        })
+    };
+    Ok([
+        find(aliases.set_offset, aliases.set_name.as_ref())?,
+        find(aliases.clear_offset, aliases.clear_name.as_ref())?,
+        find(aliases.toggle_offset, aliases.toggle_name.as_ref())?,
+    ])
+}
+
 fn render_register_mod_debug(
     register: &Register,
     access: &Access,
@@ -607,14 +812,16 @@ pub fn fields(
     access: Access,
     properties: &RegisterProperties,
     mod_items: &mut TokenStream,
+    field_values: &mut Vec<FieldValue>,
+    value_items: &mut TokenStream,
     rpath: &RegisterPath,
     index: &Index,
     config: &Config,
-) -> Result<(TokenStream, TokenStream, u64, u64)> {
+) -> Result<(TokenStream, TokenStream, TokenStream, ModifyBitmaps)> {
     let mut r_impl_items = TokenStream::new();
     let mut w_impl_items = TokenStream::new();
-    let mut zero_to_modify_fields_bitmap = 0u64;
-    let mut one_to_modify_fields_bitmap = 0u64;
+    let mut bitband_items = TokenStream::new();
+    let mut modify_bitmaps = ModifyBitmaps::default();
     let span = Span::call_site();
     let can_read = access.can_read();
     let can_write = access.can_write();
@@ -633,15 +840,23 @@ pub fn fields(
     for &f in fields.iter() {
         let mut f = f.clone();
         let mut fdpath = None;
+        let fpath = rpath.new_field(&f.name);
         if let Some(dpath) = f.derived_from.take() {
-            fdpath = derive_field(&mut f, &dpath, rpath, index)?;
+            fdpath = derive_field(&mut f, &dpath, rpath, index).element_context(
+                ElementKind::Field,
+                &fpath,
+                || format!("can't derive field '{}' from '{dpath}'", f.name),
+            )?;
         }
-        let fpath = rpath.new_field(&f.name);
         // TODO(AJM) - do we need to do anything with this range type?
         let BitRange { offset, width, .. } = f.bit_range;
 
         if f.is_single() && f.name.contains("%s") {
-            return Err(anyhow!("incorrect field {}", f.name));
+            return Err(anyhow!("incorrect field {}", f.name)).element_context(
+                ElementKind::Field,
+                &fpath,
+                || format!("can't render field '{}'", f.name),
+            );
         }
 
         let name = f.name.remove_dim();
@@ -726,7 +941,11 @@ pub fn fields(
             (false, _, true, None) => RWEnum::Write(WriteEnum::Raw),
             (true, None, true, None) => RWEnum::ReadWrite(ReadEnum::Raw, WriteEnum::Raw),
             (false, _, false, _) => {
-                return Err(anyhow!("Field {fpath} is not writtable or readable"))
+                return Err(anyhow!("Field {fpath} is not writtable or readable")).element_context(
+                    ElementKind::Field,
+                    &fpath,
+                    || format!("can't render field '{}'", f.name),
+                )
             }
         };
 
@@ -742,6 +961,9 @@ pub fn fields(
             String::new()
         };
 
+        // enumeration with a variant for each value of the field
+        let mut read_enum_ty = None;
+
         // If this field can be read, generate read proxy structure and value structure.
         if can_read {
             // collect information on items in enumeration to generate it later.
@@ -877,6 +1099,7 @@ pub fn fields(
                             let ret_ty = if has_reserved_variant {
                                 quote!(Option<#value_read_ty>)
                             } else {
+                                read_enum_ty = Some(value_read_ty.clone());
                                 quote!(#value_read_ty)
                             };
                             enum_items.extend(quote! {
@@ -1101,6 +1324,8 @@ pub fn fields(
         if can_write {
             let mut proxy_items = TokenStream::new();
             let mut safety = Safety::get(f.write_constraint.as_ref(), width);
+            // value type is an enumeration with variants
+            let mut write_enum = false;
 
             // if we writes to enumeratedValues, generate its structure if it differs from read structure.
             let value_write_ty = if let Some(ev) = rwenum.write_enum() {
@@ -1168,6 +1393,8 @@ pub fn fields(
                             }
                         }
 
+                        write_enum = !variants.is_empty();
+
                         // for each variant defined, generate a write function to this field.
                         for v in &variants {
                             let pc = &v.pc;
@@ -1341,8 +1568,103 @@ pub fn fields(
                 });
             }
 
+            // Generate const setters of register values
+            if config.const_values {
+                let members = match &f {
+                    Field::Array(f, de) => svd::field::expand(f, de)
+                        .map(|fi| {
+                            let doc = fi.description.as_deref().unwrap_or(&fi.name);
+                            (
+                                field_accessor(&fi.name, config, span),
+                                fi.bit_offset() as u64,
+                                description_with_bits(doc, fi.bit_offset() as u64, width),
+                            )
+                        })
+                        .collect(),
+                    Field::Single(_) => vec![(
+                        name_snake_case.clone(),
+                        offset,
+                        description_with_bits(description_raw, offset, width),
+                    )],
+                };
+                let (value_ty, unsafety) = if write_enum {
+                    (&value_write_ty, None)
+                } else {
+                    (&fty, (safety != Safety::Safe).then(|| quote!(unsafe)))
+                };
+                let rsize = properties.size.unwrap_or(32).max(8).to_ty_width()?;
+                let rty = rsize.to_ty()?;
+                let value = if width == 1 || write_enum || use_cast {
+                    quote!((value as #rty))
+                } else {
+                    quote!(value)
+                };
+                let value = if width == 1 || write_enum || !use_mask {
+                    value
+                } else {
+                    quote!((#value & #hexmask))
+                };
+                for (name, offset, doc) in members {
+                    let keep_mask = !(mask << offset) & (u64::MAX >> (64 - rsize));
+                    let value = if offset != 0 {
+                        let offset = unsuffixed(offset);
+                        quote!(#value << #offset)
+                    } else {
+                        value.clone()
+                    };
+                    let value = if keep_mask != 0 {
+                        let keep_mask = util::hex(keep_mask);
+                        quote!(self.bits() & #keep_mask | #value)
+                    } else {
+                        value
+                    };
+                    value_items.extend(quote! {
+                        #[doc = #doc]
+                        #inline
+                        pub const #unsafety fn #name(self, value: #value_ty) -> Self {
+                            unsafe { Self::from_bits(#value) }
+                        }
+                    });
+                }
+            }
+
+            // Generate bit-band alias writers for fields which accept both values
+            let any_value = rwenum.write_enum().map_or(true, |ev| {
+                let evs = ev.values();
+                evs.default_value().is_some() || enums_to_map(evs).len() == 1 << width
+            });
+            if config.bitband && width == 1 && safety == Safety::Safe && any_value {
+                let bb_name = Ident::new(&format!("{name_snake_case}_bb"), span);
+                if let Field::Array(_, de) = &f {
+                    let offset_calc = calculate_offset(de.dim_increment, offset, false);
+                    let doc = format!(
+                        "Field `{name}{brief_suffix}` written through the bit-band alias region"
+                    );
+                    let dim = unsuffixed(de.dim);
+                    bitband_items.extend(quote! {
+                        #[doc = #doc]
+                        #inline
+                        pub fn #bb_name(&self, n: u8) -> crate::BitBand<#regspec_ty> {
+                            #[allow(clippy::no_effect)]
+                            [(); #dim][n as usize];
+                            crate::BitBand::new(self, #offset_calc)
+                        }
+                    });
+                } else {
+                    let doc = format!("Field `{name}` written through the bit-band alias region");
+                    let offset = unsuffixed(offset);
+                    bitband_items.extend(quote! {
+                        #[doc = #doc]
+                        #inline
+                        pub fn #bb_name(&self) -> crate::BitBand<#regspec_ty> {
+                            crate::BitBand::new(self, #offset)
+                        }
+                    });
+                }
+            }
+
             // Update register modify bit masks
-            let offsets = match f {
+            let offsets = match &f {
                 MaybeArray::Array(info, dim) => (0..dim.dim)
                     .map(|i| i * dim.dim_increment + info.bit_offset())
                     .collect(),
@@ -1351,25 +1673,113 @@ pub fn fields(
             for o in offsets {
                 let bitmask = (u64::MAX >> (64 - width)) << o;
                 use ModifiedWriteValues::*;
-                match mwv {
-                    Modify | Set | Clear => {}
-                    OneToSet | OneToClear | OneToToggle => {
-                        one_to_modify_fields_bitmap |= bitmask;
-                    }
-                    ZeroToClear | ZeroToSet | ZeroToToggle => {
-                        zero_to_modify_fields_bitmap |= bitmask;
-                    }
-                }
+                *match mwv {
+                    Modify => continue,
+                    OneToSet => &mut modify_bitmaps.one_to_set,
+                    OneToClear => &mut modify_bitmaps.one_to_clear,
+                    OneToToggle => &mut modify_bitmaps.one_to_toggle,
+                    ZeroToSet => &mut modify_bitmaps.zero_to_set,
+                    ZeroToClear => &mut modify_bitmaps.zero_to_clear,
+                    ZeroToToggle => &mut modify_bitmaps.zero_to_toggle,
+                    Set => &mut modify_bitmaps.set,
+                    Clear => &mut modify_bitmaps.clear,
+                } |= bitmask;
+            }
+        }
+
+        // Collect members of the struct of field values
+        if config.field_structs {
+            let enum_ty =
+                read_enum_ty.filter(|_| !can_write || matches!(rwenum, RWEnum::ReadWriteCommon(_)));
+            let (ty, method) = match &enum_ty {
+                Some(enum_ty) => (quote!(#enum_ty), quote!(variant)),
+                None if width == 1 => (quote!(bool), quote!(bit)),
+                None => (quote!(#fty), quote!(bits)),
+            };
+            let safe = !can_write
+                || enum_ty.is_some()
+                || Safety::get(f.write_constraint.as_ref(), width) == Safety::Safe;
+            let members = match &f {
+                Field::Array(f, de) => svd::field::expand(f, de)
+                    .map(|fi| {
+                        let doc = fi.description.as_deref().unwrap_or(&fi.name);
+                        (
+                            field_accessor(&fi.name, config, span),
+                            fi.bit_offset() as u64,
+                            description_with_bits(doc, fi.bit_offset() as u64, width),
+                        )
+                    })
+                    .collect(),
+                Field::Single(_) => vec![(
+                    name_snake_case.clone(),
+                    offset,
+                    description_with_bits(description_raw, offset, width),
+                )],
+            };
+            for (name, offset, doc) in members {
+                let read = if can_read {
+                    quote!(r.#name().#method())
+                } else {
+                    let offset = unsuffixed(offset);
+                    let cast = if width == 1 {
+                        quote!(!= 0)
+                    } else {
+                        quote!(as #fty)
+                    };
+                    quote!(((r.bits() >> #offset) & #hexmask) #cast)
+                };
+                let write = can_write.then(|| quote!(.#name().#method(fields.#name)));
+                field_values.push(FieldValue {
+                    name,
+                    ty: ty.clone(),
+                    doc,
+                    read,
+                    write,
+                    safe,
+                });
             }
         }
     }
 
-    Ok((
-        r_impl_items,
-        w_impl_items,
-        zero_to_modify_fields_bitmap,
-        one_to_modify_fields_bitmap,
-    ))
+    Ok((r_impl_items, w_impl_items, bitband_items, modify_bitmaps))
+}
+
+/// Bits of fields of the register with each of modified write values
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ModifyBitmaps {
+    pub one_to_set: u64,
+    pub one_to_clear: u64,
+    pub one_to_toggle: u64,
+    pub zero_to_set: u64,
+    pub zero_to_clear: u64,
+    pub zero_to_toggle: u64,
+    pub set: u64,
+    pub clear: u64,
+}
+
+impl ModifyBitmaps {
+    /// Bits of fields modified by writing 1
+    pub fn one_to_modify(&self) -> u64 {
+        self.one_to_set | self.one_to_clear | self.one_to_toggle
+    }
+
+    /// Bits of fields modified by writing 0
+    pub fn zero_to_modify(&self) -> u64 {
+        self.zero_to_set | self.zero_to_clear | self.zero_to_toggle
+    }
+}
+
+/// Member of the struct of field values of the register
+pub struct FieldValue {
+    name: Ident,
+    ty: TokenStream,
+    doc: String,
+    /// Value from the reader `r`
+    read: TokenStream,
+    /// Writer call setting the value from `fields`
+    write: Option<TokenStream>,
+    /// Any value can be written
+    safe: bool,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -0,0 +1,423 @@
+//! Reading of IP-XACT (IEEE 1685) register descriptions
+//!
+//! Every `addressBlock` of the component's memory maps becomes a peripheral, `registerFile`s
+//! become clusters. Both the 2009 (`spirit`) and the 2014/2022 (`ipxact`) schemas are accepted,
+//! elements are matched by their local names. Values must be constants: decimal, `0x` or
+//! Verilog-style (`'h1F`, `8'b1010`) numbers. Addresses are counted in `addressUnitBits` of the
+//! memory map (8 by default), which must be the same for all the memory maps.
+
+use anyhow::{anyhow, bail, Context, Result};
+use roxmltree::{Document, Node};
+
+use crate::svd::{
+    self, Access, AddressBlock, AddressBlockUsage, BitRange, Cluster, ClusterInfo, DimElement,
+    EnumeratedValue, EnumeratedValues, FieldInfo, ModifiedWriteValues, PeripheralInfo, ReadAction,
+    Register, RegisterCluster, RegisterInfo, Usage, ValidateLevel,
+};
+
+/// Makes a device from an IP-XACT component
+pub fn parse(input: &str) -> Result<svd::Device> {
+    let doc = Document::parse(input).context("Invalid XML")?;
+    let component = doc.root_element();
+    if component.tag_name().name() != "component" {
+        bail!(
+            "Expected IP-XACT `component`, found `{}`",
+            component.tag_name().name()
+        );
+    }
+
+    let mut peripherals = Vec::new();
+    let mut address_unit_bits = None;
+    for map in children(component, "memoryMaps").flat_map(|m| children(m, "memoryMap")) {
+        let unit_bits = opt_u32(map, "addressUnitBits")
+            .with_context(|| location(&doc, map))?
+            .unwrap_or(8);
+        if unit_bits == 0 {
+            bail!("{}: `addressUnitBits` can't be 0", location(&doc, map));
+        }
+        match address_unit_bits {
+            Some(bits) if bits != unit_bits => {
+                bail!("Memory maps with different `addressUnitBits` are not supported")
+            }
+            _ => address_unit_bits = Some(unit_bits),
+        }
+        for block in children(map, "addressBlock") {
+            let p = peripheral(block, unit_bits).with_context(|| location(&doc, block))?;
+            peripherals.push(svd::Peripheral::Single(p));
+        }
+    }
+
+    let mut device = svd::Device::builder()
+        .name(text(component, "name")?)
+        .peripherals(peripherals)
+        .build(ValidateLevel::Disabled)?;
+    if let Some(bits) = address_unit_bits {
+        device.address_unit_bits = bits;
+    }
+    if let Some(vendor) = opt_text(component, "vendor") {
+        device.vendor = Some(vendor);
+    }
+    if let Some(version) = opt_text(component, "version") {
+        device.version = version;
+    }
+    if let Some(description) = opt_text(component, "description") {
+        device.description = description;
+    }
+    Ok(device)
+}
+
+fn peripheral(block: Node, unit_bits: u32) -> Result<PeripheralInfo> {
+    let mut p = PeripheralInfo::builder()
+        .name(text(block, "name")?)
+        .base_address(number(block, "baseAddress")?)
+        .build(ValidateLevel::Disabled)?;
+    p.description = opt_text(block, "description");
+    p.default_register_properties.size = opt_u32(block, "width")?;
+    p.default_register_properties.access = opt_access(block)?;
+    p.address_block = Some(vec![AddressBlock::builder()
+        .offset(0)
+        .size(u32_number(block, "range")?)
+        .usage(AddressBlockUsage::Registers)
+        .build(ValidateLevel::Disabled)?]);
+    p.registers = Some(registers(block, unit_bits)?);
+    Ok(p)
+}
+
+/// Registers and register files of an address block or a register file
+fn registers(parent: Node, unit_bits: u32) -> Result<Vec<RegisterCluster>> {
+    let mut registers = Vec::new();
+    for node in parent.children().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "register" => {
+                let (info, dim) =
+                    register(node, unit_bits).with_context(|| location(node.document(), node))?;
+                registers.push(RegisterCluster::Register(match dim {
+                    Some(dim) => Register::Array(info, dim),
+                    None => Register::Single(info),
+                }));
+            }
+            "registerFile" => {
+                let (info, dim) =
+                    cluster(node, unit_bits).with_context(|| location(node.document(), node))?;
+                registers.push(RegisterCluster::Cluster(match dim {
+                    Some(dim) => Cluster::Array(info, dim),
+                    None => Cluster::Single(info),
+                }));
+            }
+            _ => {}
+        }
+    }
+    Ok(registers)
+}
+
+fn cluster(node: Node, unit_bits: u32) -> Result<(ClusterInfo, Option<DimElement>)> {
+    let mut info = ClusterInfo::builder()
+        .name(text(node, "name")?)
+        .address_offset(u32_number(node, "addressOffset")?)
+        .children(registers(node, unit_bits)?)
+        .build(ValidateLevel::Disabled)?;
+    info.description = opt_text(node, "description");
+    let dim = dim(node, || u32_number(node, "range"))?;
+    if dim.is_some() {
+        info.name.push_str("[%s]");
+    }
+    Ok((info, dim))
+}
+
+fn register(node: Node, unit_bits: u32) -> Result<(RegisterInfo, Option<DimElement>)> {
+    let mut info = RegisterInfo::builder()
+        .name(text(node, "name")?)
+        .address_offset(u32_number(node, "addressOffset")?)
+        .build(ValidateLevel::Disabled)?;
+    info.description = opt_text(node, "description");
+    let size = u32_number(node, "size")?;
+    info.properties.size = Some(size);
+    info.properties.access = opt_access(node)?;
+
+    // 2009 schema has register reset, newer ones have resets of fields
+    if let Some(reset) = child(node, "reset") {
+        info.properties.reset_value = Some(number(reset, "value")?);
+        info.properties.reset_mask = opt_number(reset, "mask")?;
+    }
+    let mut fields = Vec::new();
+    let mut reset_value = 0;
+    let mut reset_mask = 0;
+    for node in children(node, "field") {
+        let (f, reset) = field(node).with_context(|| location(node.document(), node))?;
+        if let Some((value, mask)) = reset {
+            let field_mask = (u64::MAX >> (64 - f.bit_range.width)) & mask;
+            reset_value |= (value & field_mask) << f.bit_range.offset;
+            reset_mask |= field_mask << f.bit_range.offset;
+        }
+        fields.push(svd::Field::Single(f));
+    }
+    if info.properties.reset_value.is_none() && reset_mask != 0 {
+        info.properties.reset_value = Some(reset_value);
+        info.properties.reset_mask = Some(reset_mask);
+    }
+    if !fields.is_empty() {
+        info.fields = Some(fields);
+    }
+
+    // elements of the array follow each other in address units of the memory map
+    let dim = dim(node, || {
+        if size % unit_bits != 0 {
+            bail!("Register size {size} is not a multiple of addressUnitBits {unit_bits}");
+        }
+        Ok(size / unit_bits)
+    })?;
+    if dim.is_some() {
+        info.name.push_str("[%s]");
+    }
+    Ok((info, dim))
+}
+
+/// Field and its reset value with mask
+fn field(node: Node) -> Result<(FieldInfo, Option<(u64, u64)>)> {
+    let offset = u32_number(node, "bitOffset")?;
+    let width = u32_number(node, "bitWidth")?;
+    if width == 0 || width > 64 {
+        bail!("Invalid bitWidth {width}");
+    }
+    let mut f = FieldInfo::builder()
+        .name(text(node, "name")?)
+        .bit_range(BitRange::from_offset_width(offset, width))
+        .build(ValidateLevel::Disabled)?;
+    f.description = opt_text(node, "description");
+    f.access = opt_access(node)?;
+    if let Some(mwv) = opt_text(node, "modifiedWriteValue") {
+        f.modified_write_values = Some(
+            ModifiedWriteValues::parse_str(&mwv)
+                .ok_or_else(|| anyhow!("Unknown modifiedWriteValue `{mwv}`"))?,
+        );
+    }
+    if let Some(action) = opt_text(node, "readAction") {
+        f.read_action = Some(
+            ReadAction::parse_str(&action)
+                .ok_or_else(|| anyhow!("Unknown readAction `{action}`"))?,
+        );
+    }
+
+    let mut evs: Vec<EnumeratedValues> = Vec::new();
+    for node in children(node, "enumeratedValues").flat_map(|e| children(e, "enumeratedValue")) {
+        let usage = match node.attribute("usage").unwrap_or("read-write") {
+            "read" => Usage::Read,
+            "write" => Usage::Write,
+            "read-write" => Usage::ReadWrite,
+            usage => bail!("Unknown usage `{usage}`"),
+        };
+        let mut ev = EnumeratedValue::builder()
+            .name(text(node, "name")?)
+            .build(ValidateLevel::Disabled)?;
+        ev.description = opt_text(node, "description");
+        ev.value = Some(number(node, "value")?);
+        match evs.iter_mut().find(|e| e.usage == Some(usage)) {
+            Some(e) => e.values.push(ev),
+            None => {
+                let mut e = EnumeratedValues::builder()
+                    .values(vec![ev])
+                    .build(ValidateLevel::Disabled)?;
+                e.usage = Some(usage);
+                evs.push(e);
+            }
+        }
+    }
+    f.enumerated_values = evs;
+
+    let reset = children(node, "resets")
+        .flat_map(|r| children(r, "reset"))
+        .next()
+        .map(|reset| -> Result<_> {
+            Ok((
+                number(reset, "value")?,
+                opt_number(reset, "mask")?.unwrap_or(u64::MAX),
+            ))
+        })
+        .transpose()?;
+    Ok((f, reset))
+}
+
+/// `dim` of a register or a register file. Elements of IP-XACT arrays are contiguous
+fn dim(node: Node, increment: impl FnOnce() -> Result<u32>) -> Result<Option<DimElement>> {
+    let dims = children(node, "dim")
+        .map(|d| parse_number(d.text().unwrap_or_default()))
+        .collect::<Result<Vec<_>>>()?;
+    match dims.as_slice() {
+        [] => Ok(None),
+        [dim] => Ok(Some(
+            DimElement::builder()
+                .dim(to_u32(*dim, "dim")?)
+                .dim_increment(increment()?)
+                .build(ValidateLevel::Disabled)?,
+        )),
+        _ => bail!("Multidimensional arrays are not supported"),
+    }
+}
+
+fn location(doc: &Document, node: Node) -> String {
+    let pos = doc.text_pos_at(node.range().start);
+    let name = opt_text(node, "name").unwrap_or_default();
+    format!(
+        "In `{}` {name} at line {}, column {}",
+        node.tag_name().name(),
+        pos.row,
+        pos.col
+    )
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |n| n.is_element() && n.tag_name().name() == name)
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &'a str) -> Option<Node<'a, 'input>> {
+    children(node, name).next()
+}
+
+fn opt_text(node: Node, name: &str) -> Option<String> {
+    child(node, name)
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_string())
+}
+
+fn text(node: Node, name: &str) -> Result<String> {
+    opt_text(node, name).ok_or_else(|| anyhow!("`{name}` is missing"))
+}
+
+fn opt_number(node: Node, name: &str) -> Result<Option<u64>> {
+    opt_text(node, name)
+        .map(|s| parse_number(&s).with_context(|| format!("Invalid `{name}`")))
+        .transpose()
+}
+
+fn number(node: Node, name: &str) -> Result<u64> {
+    opt_number(node, name)?.ok_or_else(|| anyhow!("`{name}` is missing"))
+}
+
+fn opt_u32(node: Node, name: &str) -> Result<Option<u32>> {
+    opt_number(node, name)?
+        .map(|value| to_u32(value, name))
+        .transpose()
+}
+
+fn u32_number(node: Node, name: &str) -> Result<u32> {
+    to_u32(number(node, name)?, name)
+}
+
+fn to_u32(value: u64, name: &str) -> Result<u32> {
+    u32::try_from(value).map_err(|_| anyhow!("`{name}` {value:#x} doesn't fit in 32 bits"))
+}
+
+fn opt_access(node: Node) -> Result<Option<Access>> {
+    opt_text(node, "access")
+        .map(|s| Access::parse_str(&s).ok_or_else(|| anyhow!("Unknown access `{s}`")))
+        .transpose()
+}
+
+/// Parses decimal, `0x`/`0b` prefixed and Verilog-style numbers
+fn parse_number(s: &str) -> Result<u64> {
+    let s = s.trim().replace('_', "");
+    let (radix, digits) = if let Some((_, v)) = s.split_once('\'') {
+        let (base, digits) = v.split_at(v.len().min(1));
+        let radix = match base {
+            "h" | "H" => 16,
+            "b" | "B" => 2,
+            "o" | "O" => 8,
+            "d" | "D" => 10,
+            _ => bail!("Unknown number format `{s}`"),
+        };
+        (radix, digits)
+    } else if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        (16, hex)
+    } else if let Some(bin) = s.strip_prefix("0b").or_else(|| s.strip_prefix("0B")) {
+        (2, bin)
+    } else {
+        (10, s.as_str())
+    };
+    u64::from_str_radix(digits, radix)
+        .map_err(|_| anyhow!("`{s}` is not a constant number, expressions are not supported"))
+}
+
+#[test]
+fn parse_component() {
+    let xml = r#"
+<ipxact:component xmlns:ipxact="http://www.accellera.org/XMLSchema/IPXACT/1685-2014">
+  <ipxact:vendor>acme</ipxact:vendor>
+  <ipxact:library>soft</ipxact:library>
+  <ipxact:name>core</ipxact:name>
+  <ipxact:version>1.0</ipxact:version>
+  <ipxact:memoryMaps>
+    <ipxact:memoryMap>
+      <ipxact:name>regs</ipxact:name>
+      <ipxact:addressBlock>
+        <ipxact:name>UART</ipxact:name>
+        <ipxact:baseAddress>'h4000_0000</ipxact:baseAddress>
+        <ipxact:range>0x100</ipxact:range>
+        <ipxact:width>32</ipxact:width>
+        <ipxact:register>
+          <ipxact:name>CTRL</ipxact:name>
+          <ipxact:addressOffset>0x0</ipxact:addressOffset>
+          <ipxact:size>32</ipxact:size>
+          <ipxact:field>
+            <ipxact:name>MODE</ipxact:name>
+            <ipxact:bitOffset>4</ipxact:bitOffset>
+            <ipxact:resets><ipxact:reset><ipxact:value>2'b10</ipxact:value></ipxact:reset></ipxact:resets>
+            <ipxact:bitWidth>2</ipxact:bitWidth>
+            <ipxact:access>read-write</ipxact:access>
+            <ipxact:enumeratedValues>
+              <ipxact:enumeratedValue><ipxact:name>OFF</ipxact:name><ipxact:value>0</ipxact:value></ipxact:enumeratedValue>
+              <ipxact:enumeratedValue><ipxact:name>ON</ipxact:name><ipxact:value>2</ipxact:value></ipxact:enumeratedValue>
+            </ipxact:enumeratedValues>
+          </ipxact:field>
+        </ipxact:register>
+        <ipxact:register>
+          <ipxact:name>DATA</ipxact:name>
+          <ipxact:dim>4</ipxact:dim>
+          <ipxact:addressOffset>0x10</ipxact:addressOffset>
+          <ipxact:size>32</ipxact:size>
+        </ipxact:register>
+      </ipxact:addressBlock>
+    </ipxact:memoryMap>
+  </ipxact:memoryMaps>
+</ipxact:component>"#;
+    let device = parse(xml).unwrap();
+    assert_eq!(device.name, "core");
+    let uart = &device.peripherals[0];
+    assert_eq!(uart.base_address, 0x4000_0000);
+    let registers = uart.registers.as_ref().unwrap();
+    let RegisterCluster::Register(ctrl) = &registers[0] else {
+        panic!("CTRL is not a register")
+    };
+    assert_eq!(ctrl.properties.reset_value, Some(0x20));
+    assert_eq!(ctrl.properties.reset_mask, Some(0x30));
+    assert_eq!(
+        ctrl.fields.as_ref().unwrap()[0].enumerated_values[0]
+            .values
+            .len(),
+        2
+    );
+    assert!(
+        matches!(&registers[1], RegisterCluster::Register(Register::Array(r, dim))
+        if r.name == "DATA[%s]" && dim.dim == 4 && dim.dim_increment == 4)
+    );
+
+    // arrays advance in address units of the memory map
+    let xml16 = xml.replace(
+        "</ipxact:addressBlock>",
+        "</ipxact:addressBlock><ipxact:addressUnitBits>16</ipxact:addressUnitBits>",
+    );
+    let device = parse(&xml16).unwrap();
+    assert_eq!(device.address_unit_bits, 16);
+    let registers = device.peripherals[0].registers.as_ref().unwrap();
+    assert!(matches!(&registers[1],
+        RegisterCluster::Register(Register::Array(_, dim)) if dim.dim_increment == 2));
+
+    let too_far = xml.replace(
+        "0x10</ipxact:addressOffset>",
+        "0x1_0000_0000</ipxact:addressOffset>",
+    );
+    assert!(parse(&too_far).is_err());
+}
@@ -30,6 +30,169 @@
 //! [`form`](https://github.com/djmcgill/form) commit fcb397a or newer is required for splitting
 //! the emitted `lib.rs`.
 //!
+//! ## the `--split` flag
+//!
+//! Instead of using [`form`], `svd2rust --split` can write every module into a file of its own:
+//! one file per peripheral, with its registers and clusters in a subdirectory, and `generic.rs`.
+//! `mod` declarations keep the same module tree as the inline `lib.rs`, so the output directory can be
+//! used as the `src` directory of the device crate directly:
+//!
+//! ``` text
+//! $ svd2rust --split -i STM32F30x.svd -o src/
+//! ```
+//!
+//! ## the `--make-crate` flag
+//!
+//! With `--make-crate` the output directory becomes a complete device crate: `Cargo.toml`,
+//! `build.rs` and `device.x` are placed in its root and the generated code goes to `src`.
+//! `Cargo.toml` contains the dependencies required by the selected `--target`, the `rt` and
+//! `critical-section` features, the features enabled by `--feature-group` and `--feature-peripheral`
+//! and the vendor, version and license of the device in `[package.metadata.svd]`. The package name
+//! is taken from the device name unless `--crate-name` is specified.
+//!
+//! ``` text
+//! $ svd2rust --make-crate --split -i STM32F30x.svd -o stm32f30x/
+//! ```
+//!
+//! ## the `--check` flag
+//!
+//! `svd2rust --check` renders the same files as a normal run, but instead of writing them compares
+//! them with the files in the output directory. Differing files are reported with a short unified
+//! diff and Rust files in the module tree of the crate that are not generated anymore (e.g.
+//! modules of removed peripherals with `--split`) are listed. Then `svd2rust` exits with a non-zero
+//! code, which is useful in CI to verify that a committed PAC is in sync with its SVD and settings.
+//!
+//! ## the `--provenance` flag
+//!
+//! With `--provenance` the generated crate gets a `provenance` module with the svd2rust version,
+//! SHA-256 hashes of the input SVD, of the settings file and of the included and patch files and
+//! the effective configuration in JSON. The same information is written to `svd2rust.json` next to
+//! the generated code, so it's easy to find out whether a PAC is stale and to regenerate it with
+//! exactly the same options. Paths of input and output files and options which don't change the
+//! generated code, like `--check`, are not recorded, so the record doesn't depend on where the
+//! PAC is generated.
+//!
+//! ## the `--include` option
+//!
+//! Core and vendor peripherals of some parts are described in separate files.
+//! `--include FILE` (can be repeated, `include` in `svd2rust.toml`) merges peripherals of
+//! another SVD (or YAML, JSON) file into the input device before it is validated.
+//! Peripherals defined in several files and overlapping address ranges of peripherals
+//! from different files are reported as errors.
+//!
+//! ## IP-XACT input
+//!
+//! Register descriptions in IP-XACT (IEEE 1685) are read with `--source-type ipxact` or from files
+//! with `.ipxact`, `.xact` or `.ipxact.xml` extension. Each `addressBlock` of the component becomes
+//! a peripheral, `registerFile`s become clusters. See the [`ipxact`] module for details.
+//!
+//! ## CMSIS-Packs
+//!
+//! Instead of the input file, a CMSIS-Pack can be given with `--pack PATH` (an unpacked pack
+//! directory or a `.pdsc` file) and `--pack-device NAME`. The SVD file of the device is found
+//! through the pack description, and a starter `memory.x` with memory regions of the device
+//! is written next to `device.x`. The `FLASH` and `RAM` regions required by `cortex-m-rt` are
+//! picked from the startup and the default writable regions.
+//!
+//! ## the `--patch` option
+//!
+//! Vendor SVD files often need fixes. `--patch FILE` (can be repeated, `patches` in `svd2rust.toml`)
+//! applies [svdtools](https://github.com/rust-embedded/svdtools)-style YAML patches to the device
+//! before the code is generated. Supported commands are `_modify`, `_delete`, `_add`, `_derive`,
+//! `_array` (collect registers into an array) and `_include`; fields can get enumerated values.
+//! See the [`patch`] module for the format.
+//!
+//! ## Filters
+//!
+//! To generate code only for a part of a big device, add include and exclude lists to the
+//! `filter` section of `svd2rust.toml`:
+//!
+//! ``` toml
+//! [filter.peripherals]
+//! include = ["GPIO*", "USART1", "/TIM[0-9]+/"]
+//! [filter.registers]
+//! exclude = ["*.DBG*"]
+//! [filter.interrupts]
+//! exclude = ["LCD"]
+//! ```
+//!
+//! Lists are available for `peripherals`, `clusters`, `registers` and `interrupts`. Patterns
+//! are globs or regular expressions in slashes, patterns with `.` are matched against the
+//! full path of the element. Elements that kept ones are derived from are kept automatically.
+//!
+//! ## Moving peripherals
+//!
+//! `base_address_shift` moves all the peripherals. Individual peripherals can be moved in the
+//! `peripherals` section of the settings file, where keys are peripheral names or globs:
+//!
+//! ``` yaml
+//! peripherals:
+//!   UART*:
+//!     offset: 0x1000
+//!   SPI0:
+//!     base_address: 0x41000000
+//!     instances:
+//!       SPI2: 0x42000000
+//! ```
+//!
+//! `instances` adds peripherals with the register block of the original one at addresses not
+//! listed in the SVD file. Settings of all the keys matching a peripheral are combined in
+//! alphabetical order of keys, a value given by a later key replaces the one of an earlier key.
+//! An absolute `base_address` wins over `offset` of any key.
+//!
+//! ## Relocatable peripherals
+//!
+//! With `--relocatable` peripheral handles store the pointer to their register block, so the
+//! same types can be used for blocks found at runtime (PCIe BARs, `mmap` of UIO devices, soft-core
+//! IP from a devicetree):
+//!
+//! ```ignore
+//! let base = map_device()? as *const pac::uart0::RegisterBlock;
+//! let uart = unsafe { pac::UART0::from_ptr(base) };
+//! uart.ctrl().write(|w| w.en().set_bit());
+//! assert_eq!(uart.as_ptr(), base);
+//! ```
+//!
+//! `steal` and `Peripherals::take` still create instances at addresses from the SVD file.
+//!
+//! ## Endianness
+//!
+//! Registers are accessed in the byte order of the target by default (`Endian::Native`). Register
+//! specs of big-endian devices (`cpu.endian` of the SVD file) and of peripherals with a byte order
+//! from the settings file carry it as `RegisterSpec::ENDIAN`. The `vcell` and `ptr` backends and
+//! the atomic API swap bytes of these registers on access when it differs from the byte order of
+//! the target, and compile to plain accesses when it doesn't. The byte order of the device and of
+//! individual peripherals, e.g. behind a byte-swapping bus bridge, can be set in the settings
+//! file:
+//!
+//! ``` yaml
+//! endian: big
+//! peripherals:
+//!   PCI_*:
+//!     endian: little
+//! ```
+//!
+//! Registers of `sim` and `transport` backends are always accessed in the byte order of the
+//! target.
+//!
+//! ## Using from a build script
+//!
+//! `svd2rust` can also be used as a library from `build.rs` of the device crate with [`Builder`].
+//! It writes a single file to `OUT_DIR` that can be included in `lib.rs` with `include!`,
+//! and tells Cargo to rerun the build script when the SVD or the settings file changes:
+//!
+//! ``` ignore
+//! // build.rs
+//! svd2rust::Builder::new("STM32F30x.svd")
+//!     .target(svd2rust::Target::CortexM)
+//!     .build()
+//!     .unwrap();
+//!
+//! // src/lib.rs
+//! #![no_std]
+//! include!(concat!(env!("OUT_DIR"), "/stm32f30x.rs"));
+//! ```
+//!
 //! ## target = cortex-m
 //!
 //! When targeting the Cortex-M architecture, `svd2rust` will generate three files in the current
@@ -41,10 +204,12 @@
 //! - `lib.rs`, the generated code.
 //!
 //! All these files must be included in the same device crate. The `lib.rs` file contains several
-//! inlined modules and its not formatted. It's recommended to split it out using the [`form`] tool
-//! and then format the output using `rustfmt` / `cargo fmt`:
+//! inlined modules. It's recommended to split it out using the `--split` flag or the [`form`] tool.
+//! The generated code is already formatted with [`prettyplease`], so running `rustfmt` afterwards
+//! is optional:
 //!
 //! [`form`]: https://crates.io/crates/form
+//! [`prettyplease`]: https://crates.io/crates/prettyplease
 //!
 //! ``` text
 //! $ svd2rust -i STM32F30x.svd
@@ -52,8 +217,6 @@
 //! $ rm -rf src
 //!
 //! $ form -i lib.rs -o src/ && rm lib.rs
-//!
-//! $ cargo fmt
 //! ```
 //!
 //! The resulting crate must provide an opt-in `rt` feature and depend on these crates:
@@ -99,8 +262,7 @@
 //! - `lib.rs`, the generated code.
 //!
 //! All these files must be included in the same device crate. The `lib.rs` file contains several
-//! inlined modules and its not formatted. It's recommend to split it out using the [`form`] tool
-//! and then format the output using `rustfmt` / `cargo fmt`:
+//! inlined modules. It's recommend to split it out using the `--split` flag or the [`form`] tool:
 //!
 //! [`form`]: https://crates.io/crates/form
 //!
@@ -116,8 +278,6 @@
 //! $ form -i lib.rs -o src/ && rm lib.rs
 //!
 //! $ mv generic.rs src/
-//!
-//! $ cargo fmt
 //! ```
 //!
 //! The resulting crate must provide opt-in `rt` feature and depend on these crates:
@@ -144,7 +304,7 @@
 //! ## Other targets
 //!
 //! When the target is riscv or none `svd2rust` will emit only the `lib.rs` file. Like in
-//! the `cortex-m` case, we recommend you use `form` and `rustfmt` on the output.
+//! the `cortex-m` case, we recommend you use `--split` or `form` on the output.
 //!
 //! The resulting crate must provide an opt-in `rt` feature and depend on these crates:
 //!
@@ -409,6 +569,64 @@
 //! i2c1.cr2().modify(|r, w| w.stop().bit(!r.stop().bit()));
 //! ```
 //!
+//! ## `wait_until`
+//!
+//! The `wait_until` method reads the register until the closure returns `true` for the read
+//! value and returns the value. `wait_until_bounded` stops after the given number of reads with
+//! `Timeout` error and `wait_until_async` yields to other tasks between reads.
+//!
+//! ```ignore
+//! // wait for the end of the transfer
+//! i2c1.isr().wait_until(|r| r.tc().bit_is_set());
+//!
+//! // give up after 1000 reads
+//! let isr = i2c1.isr().wait_until_bounded(|r| r.txe().bit_is_set(), 1000)?;
+//!
+//! // in async tasks
+//! i2c1.isr().wait_until_async(|r| r.busy().bit_is_clear()).await;
+//! ```
+//!
+//! ## Field structs
+//!
+//! With the `--field-structs` flag each register module gets a `Copy` struct `Fields` with a
+//! member for each field: `bool` for single bits, the enumeration if it has a variant for each
+//! value of the field, or the raw value otherwise. `R::fields` reads all fields and
+//! `W::set_fields` writes all writable fields, so register configurations can be stored and
+//! compared as ordinary data. `read_value` and `write_value` of registers do the same in a
+//! single call:
+//!
+//! ```ignore
+//! const UART_CONFIG: cr1::Fields = cr1::Fields {
+//!     ue: true,
+//!     m: M::Bit8,
+//!     pce: false,
+//!     // ...
+//! };
+//!
+//! usart1.cr1().write_value(UART_CONFIG);
+//! assert_eq!(usart1.cr1().read_value(), UART_CONFIG);
+//! ```
+//!
+//! `W::set_fields` and `write_value` are available when any value of the members can be written.
+//! Registers with raw fields without write constraints are written with
+//! `write(|w| unsafe { w.set_fields_unchecked(fields) })`.
+//!
+//! ## Const values
+//!
+//! With the `--const-values` flag each writable register module gets a `RESET` constant of type
+//! `crate::Value<SPEC>`, the initial value of the writer, and `Value` gets a `const fn` setter for
+//! each writable field. Register configurations can then be computed at compile time and written
+//! with `W::set_value`:
+//!
+//! ```ignore
+//! const CR1: Value<cr1::Cr1Spec> = cr1::RESET.ue(true).m(M::Bit8);
+//!
+//! usart1.cr1().write(|w| w.set_value(CR1));
+//! ```
+//!
+//! Setters of fields that take raw values which can be invalid are `unsafe`. Accessors of fields
+//! named `from_bits` and `set_value` get `_` suffix with this flag.
+//!
 //! # enumeratedValues
 //!
 //! If your SVD uses the `<enumeratedValues>` feature, then the API will be *extended* to provide
@@ -514,8 +732,9 @@
 //! The `--atomics` flag can be passed to `svd2rust` to extends the register API with operations to
 //! atomically set, clear, and toggle specific bits.  The atomic operations allow limited
 //! modification of register bits without read-modify-write sequences. As such, they can be
-//! concurrently called on different bits in the same register without data races. This flag won't
-//! work for RISCV chips without the atomic extension.
+//! concurrently called on different bits in the same register without data races. With the default
+//! `--atomics-backend` this flag won't work for RISCV chips without the atomic extension. It
+//! needs memory-mapped registers, so it is an error with `sim` and `transport` backends.
 //!
 //! The `--atomics-feature` flag can also be specified to include atomics implementations conditionally
 //! behind the supplied feature name.
@@ -523,6 +742,124 @@
 //! `portable-atomic` v0.3.16 must be added to the dependencies, with default features off to
 //! disable the `fallback` feature.
 //!
+//! `--atomics-backend` chooses the implementation of atomic operations:
+//!
+//! - `portable` (default) uses atomic types of `portable-atomic`.
+//! - `native` uses atomic types of `core`. Operations are available only for register widths
+//!   with `target_has_atomic`.
+//! - `critical-section` reads and writes the register in `critical_section::with`. This works on
+//!   cores without atomic read-modify-write instructions, like thumbv6m, MSP430 or RISC-V without
+//!   the A extension. The PAC must depend on `critical-section`. Crates generated with
+//!   `--make-crate` depend on it, or enable it with the `--atomics-feature` feature.
+//! - `auto` uses atomic types of `core` for widths the target supports and critical sections for
+//!   the rest, so one PAC supports all cores of a family.
+//!
+//! Chips with hardware set, clear and toggle aliases of registers can describe them in the
+//! `aliases` section of the settings file, keyed by peripheral names or globs. Matching keys are
+//! combined as in the `peripherals` section, so `"*"` can be refined by more specific keys. An
+//! alias is a window at a fixed offset from the register or a sibling
+//! register named after it, where `{}` is replaced with the name of the register:
+//!
+//! ``` yaml
+//! aliases:
+//!   "*":           # RP2040
+//!     toggle_offset: 0x1000
+//!     set_offset: 0x2000
+//!     clear_offset: 0x3000
+//!   PORT?:         # PIC32
+//!     set_name: "{}SET"
+//!     clear_name: "{}CLR"
+//!     toggle_name: "{}INV"
+//! ```
+//!
+//! `set_bits`, `clear_bits` and `toggle_bits` of registers with aliases write to the alias instead
+//! of using atomic instructions, so they are also available on cores without atomic instructions
+//! for the width of the register (e.g. thumbv6m with `--atomics-backend native`). Using an
+//! operation that has neither an alias nor atomic instructions is a compile error.
+//!
+//! ## the `--bitband` flag
+//!
+//! Cortex-M3 and Cortex-M4 map each bit of the first megabyte of SRAM (`0x2000_0000`) and of
+//! peripherals (`0x4000_0000`) to a word of the bit-band alias region. With `--bitband` and
+//! `--target cortex-m`, registers of peripherals lying entirely in these regions get a
+//! `<field>_bb` method for each writable single-bit field which accepts both values, i.e. that
+//! isn't restricted by a `writeConstraint` or by enumerated values. It writes the bit with a
+//! single store to the alias word, so it is interrupt-safe without critical sections or exclusive
+//! accesses:
+//!
+//! ```ignore
+//! gpioa.odr().odr5_bb().set_bit();
+//! ```
+//!
+//! The bus still reads and writes back the whole register, so the methods are not generated for
+//! registers with a `readAction` or with `oneTo*` and `zeroTo*` modified write values, nor for
+//! big-endian peripherals.
+//! `Reg::bb_write` writes any bit of a register, it panics if the register is outside of the
+//! bit-band regions. The flag is an error with other targets than `cortex-m` and with `sim` and
+//! `transport` backends.
+//!
+//! ## the `--backend` flag
+//!
+//! `--backend sim` replaces volatile accesses of `Reg` with registers simulated on the host, so
+//! drivers can be unit tested with the same PAC. The `generic` module then needs `std`. Each
+//! register starts with its reset value. Fields with modified write values (`oneToClear`,
+//! `zeroToSet`, `set`, ...) change as described, the rest of the bits keep the written value:
+//!
+//! ```ignore
+//! use pac::generic::sim::{self, Access};
+//!
+//! sim::reset_all();
+//! let uart = unsafe { pac::UART0::steal() };
+//! uart.sr().sim_inject_reads([0b1, 0b1, 0b0]); // busy for two reads
+//! driver.send(&uart, 0x55);
+//! assert_eq!(
+//!     uart.dr().sim_take_accesses(),
+//!     [Access::Write(0x55)],
+//! );
+//! ```
+//!
+//! State of registers is kept per thread and keyed by the address of the register. Peripherals
+//! dereference to zeroed host memory standing in for the register block
+//! (`generic::sim::block`), memory of peripherals is never accessed. `--atomics` is an error with
+//! this backend.
+//!
+//! With `--backend ptr` no references to registers are made. Register blocks and clusters are
+//! `Copy` wrappers of a raw pointer and accessors return `Reg` by value, which accesses the
+//! register with `read_volatile` and `write_volatile`. Driver code stays the same:
+//!
+//! ```ignore
+//! let uart = unsafe { pac::UART0::steal() };
+//! let cr = uart.cr(); // `Reg<CR_SPEC>` holding the address of the register
+//! cr.modify(|_, w| w.en().set_bit());
+//! ```
+//!
+//! `PTR` and `ptr()` of peripherals are `*mut u8` with this backend.
+//!
+//! `--backend transport` is for devices behind a bus, like I2C sensors described with SVD.
+//! Register blocks hold the address of the block and registers are accessed through
+//! `generic::transport::Interface` or `AsyncInterface` implemented by the driver. Fields are
+//! read and written with the usual readers and writers:
+//!
+//! ```ignore
+//! use pac::generic::transport::Interface;
+//!
+//! impl Interface for Bus {
+//!     type Error = I2cError;
+//!     fn read_register(&mut self, address: u64, size: u32) -> Result<u64, I2cError> { ... }
+//!     fn write_register(&mut self, address: u64, size: u32, value: u64) -> Result<(), I2cError> { ... }
+//! }
+//!
+//! let pmic = unsafe { pac::PMIC::steal() };
+//! if pmic.status().read(&mut bus)?.pgood().bit_is_set() {
+//!     pmic.ctrl().modify(&mut bus, |_, w| w.en().set_bit())?;
+//!     pmic.ctrl().write_async(&mut async_bus, |w| w.en().set_bit()).await?;
+//! }
+//! ```
+//!
+//! Addresses are in SVD address units (`ADDRESS_UNIT_BITS` in the crate root), sizes are sizes of
+//! registers in bits. Peripherals have `ADDRESS` instead of `PTR`, and with `--relocatable`
+//! `from_addr` and `addr` take and return the bus address of the register block.
+//!
 //! ## the `--impl-debug` flag
 //!
 //! The `--impl_debug` option will cause svd2rust to generate `core::fmt::Debug` implementations for
@@ -590,28 +927,67 @@
 //! To revert old behavior for all identifiers you may pass `--ident-formats-theme legacy`.
 #![recursion_limit = "128"]
 
-use quote::quote;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use svd_parser::svd;
 
+pub mod builder;
 pub mod config;
+pub mod diagnostic;
+pub mod filter;
 pub mod generate;
+#[cfg(feature = "ipxact")]
+pub mod ipxact;
+pub mod merge;
+#[cfg(feature = "pack")]
+pub mod pack;
+#[cfg(feature = "yaml")]
+pub mod patch;
+#[cfg(feature = "json")]
+pub mod provenance;
+pub mod remap;
+pub mod split;
 pub mod util;
 
-pub use config::{Config, Settings, Target};
+pub use builder::Builder;
+pub use config::{AtomicsBackend, Backend, Config, Endian, Settings, Target};
 
+/// Files of the generated crate
 #[non_exhaustive]
 pub struct Generation {
-    pub lib_rs: String,
-    pub device_specific: Option<DeviceSpecific>,
+    /// Contents of the generated files, by path relative to the output directory
+    pub files: BTreeMap<PathBuf, String>,
 }
 
-#[non_exhaustive]
-pub struct DeviceSpecific {
-    pub device_x: String,
-    pub build_rs: String,
+impl Generation {
+    /// Code of the crate root (`lib.rs` or `mod.rs`)
+    pub fn lib_rs(&self) -> Option<&str> {
+        self.files
+            .iter()
+            .find(|(path, _)| {
+                path.parent()
+                    .is_some_and(|p| p.as_os_str().is_empty() || p == Path::new("src"))
+                    && (path.ends_with("lib.rs") || path.ends_with("mod.rs"))
+            })
+            .map(|(_, data)| data.as_str())
+    }
+
+    /// Writes all the files to the `dir` directory
+    pub fn write_to(&self, dir: &Path) -> Result<()> {
+        for (path, data) in &self.files {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Couldn't create directory {}", parent.display()))?;
+            }
+            std::fs::write(&path, data)
+                .with_context(|| format!("Could not write {}", path.display()))?;
+        }
+        Ok(())
+    }
 }
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 
 use crate::config::{IdentFormats, IdentFormatsTheme};
 
@@ -621,12 +997,27 @@ pub enum SvdError {
     Fmt,
     #[error("Cannot render SVD device")]
     Render(#[from] anyhow::Error),
+    /// Error in a peripheral, cluster, register or field
+    #[error("{message}")]
+    Element {
+        kind: diagnostic::ElementKind,
+        /// Path of the element, like `PERIPHERAL.REGISTER.FIELD`
+        path: String,
+        message: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    /// Error with the position of the element in the input file
+    #[error("{diagnostic}")]
+    Located {
+        diagnostic: Box<diagnostic::Diagnostic>,
+        #[source]
+        source: anyhow::Error,
+    },
 }
 
 /// Generates rust code for the specified svd content.
 pub fn generate(input: &str, config: &Config) -> Result<Generation> {
-    use std::fmt::Write;
-
     let mut config = config.clone();
 
     match config.settings_file.as_ref() {
@@ -652,33 +1043,92 @@ pub fn generate(input: &str, config: &Config) -> Result<Generation> {
     config.ident_formats = ident_formats;
 
     let device = load_from(input, &config)?;
+    render(&device, input, &config)
+}
+
+/// Renders all the files of the crate for the specified device.
+///
+/// `input` is the content the device was loaded from, its hash is recorded with
+/// the `provenance` option. Nothing is written to disk, see [`Generation::write_to`].
+pub fn render(device: &svd::Device, input: &str, config: &Config) -> Result<Generation> {
+    if config.make_crate && config.make_mod {
+        bail!("`make_crate` and `make_mod` options are mutually exclusive");
+    }
+    // sources of the crate are placed in `src`, everything else in the crate root
+    let src = Path::new(if config.make_crate { "src" } else { "" });
+    let mut files = BTreeMap::new();
+
     let mut device_x = String::new();
-    let items =
-        generate::device::render(&device, &config, &mut device_x).map_err(SvdError::Render)?;
+    let mut items = generate::device::render(device, config, &mut device_x)
+        .map_err(|e| diagnostic::locate(SvdError::Render(e).into(), input, config))?;
 
-    let mut lib_rs = String::new();
-    writeln!(
-        &mut lib_rs,
-        "{}",
-        quote! {
-            #items
+    if config.provenance {
+        #[cfg(feature = "json")]
+        {
+            let provenance = provenance::Provenance::new(input, config)?;
+            items.extend(provenance.render());
+            files.insert("svd2rust.json".into(), provenance.to_json()?);
+        }
+        #[cfg(not(feature = "json"))]
+        {
+            let _ = input;
+            bail!("Support for provenance manifest is not available because svd2rust was compiled without the json feature");
         }
-    )
-    .or(Err(SvdError::Fmt))?;
+    }
 
-    let device_specific = if device_x.is_empty() {
-        None
+    let file: syn::File = syn::parse2(items).or(Err(SvdError::Fmt))?;
+
+    let filename = if config.make_mod { "mod.rs" } else { "lib.rs" };
+    if config.split {
+        for (name, module) in split::split(file, Path::new(filename)) {
+            files.insert(src.join(name), prettyplease::unparse(&module));
+        }
     } else {
-        Some(DeviceSpecific {
-            device_x,
-            build_rs: util::build_rs().to_string(),
-        })
-    };
+        files.insert(src.join(filename), prettyplease::unparse(&file));
+    }
 
-    Ok(Generation {
-        lib_rs,
-        device_specific,
-    })
+    if config.generic_mod {
+        let generic = generate::device::render_generic(config)?;
+        files.insert(
+            src.join("generic.rs"),
+            util::format_code(generic).or(Err(SvdError::Fmt))?,
+        );
+    }
+
+    if [
+        Target::CortexM,
+        Target::Msp430,
+        Target::XtensaLX,
+        Target::RISCV,
+    ]
+    .contains(&config.target)
+    {
+        files.insert("device.x".into(), device_x + "\n");
+        files.insert(
+            "build.rs".into(),
+            util::format_code(util::build_rs()).or(Err(SvdError::Fmt))?,
+        );
+    }
+
+    if config.make_crate {
+        files.insert(
+            "Cargo.toml".into(),
+            generate::manifest::render(device, config)?,
+        );
+    } else if config.feature_group || config.feature_peripheral {
+        files.insert(
+            "features.toml".into(),
+            format!(
+                "# Below are the FEATURES generated by svd2rust base on groupName in SVD file.\n\
+                # Please copy them to Cargo.toml.\n\
+                [features]\n\
+                {}",
+                generate::manifest::features(device, config).join("")
+            ),
+        );
+    }
+
+    Ok(Generation { files })
 }
 
 /// Load a [Device](svd::Device) from a string slice with given [config](crate::config::Config).
@@ -692,31 +1142,55 @@ pub fn load_from(input: &str, config: &Config) -> Result<svd::Device> {
         ValidateLevel::Weak
     };
 
-    let mut device = match config.source_type {
+    let mut device = parse_device(input, config.source_type)?;
+    for path in &config.include {
+        let input = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read the included file {}", path.display()))?;
+        let other = parse_device(&input, SourceType::from_path(path))
+            .with_context(|| format!("Error parsing the included file {}", path.display()))?;
+        merge::merge(&mut device, other)
+            .with_context(|| format!("Cannot merge {}", path.display()))?;
+    }
+    if !config.patches.is_empty() {
+        #[cfg(feature = "yaml")]
+        patch::apply(&mut device, &config.patches)?;
+        #[cfg(not(feature = "yaml"))]
+        bail!("Support for patch files is not available because svd2rust was compiled without the yaml feature");
+    }
+    remap::apply(&mut device, &config.settings)?;
+    if !config.filter.is_empty() {
+        filter::apply(&mut device, &config.filter)?;
+    }
+    diagnostic::validate(&device, validate_level)
+        .map_err(|e| diagnostic::locate(e, input, config))?;
+    svd_parser::expand_properties(&mut device);
+    Ok(device)
+}
+
+/// Parses the device without validation
+fn parse_device(input: &str, source_type: config::SourceType) -> Result<svd::Device> {
+    use config::SourceType;
+    use svd_parser::ValidateLevel;
+
+    Ok(match source_type {
         SourceType::Xml => {
             let mut parser_config = svd_parser::Config::default();
-            parser_config.validate_level = validate_level;
+            parser_config.validate_level = ValidateLevel::Disabled;
 
             svd_parser::parse_with_config(input, &parser_config)
                 .with_context(|| "Error parsing SVD XML file".to_string())?
         }
         #[cfg(feature = "yaml")]
-        SourceType::Yaml => {
-            let device: svd::Device = serde_yaml::from_str(input)
-                .with_context(|| "Error parsing SVD YAML file".to_string())?;
-            device.validate_all(validate_level)?;
-            device
-        }
+        SourceType::Yaml => serde_yaml::from_str(input)
+            .with_context(|| "Error parsing SVD YAML file".to_string())?,
         #[cfg(feature = "json")]
-        SourceType::Json => {
-            let device: svd::Device = serde_json::from_str(input)
-                .with_context(|| "Error parsing SVD JSON file".to_string())?;
-            device.validate_all(validate_level)?;
-            device
+        SourceType::Json => serde_json::from_str(input)
+            .with_context(|| "Error parsing SVD JSON file".to_string())?,
+        #[cfg(feature = "ipxact")]
+        SourceType::IpXact => {
+            ipxact::parse(input).with_context(|| "Error parsing IP-XACT file".to_string())?
         }
-    };
-    svd_parser::expand_properties(&mut device);
-    Ok(device)
+    })
 }
 
 /// Assigns a handler to an interrupt
@@ -767,3 +1241,149 @@ macro_rules! interrupt {
         $($lvar:ident: $lty:ty = $lval:expr;)+
     }) => {};
 }
+
+#[test]
+fn generated_crates_compile() {
+    let svd = r#"
+<device>
+  <name>TEST</name>
+  <width>32</width>
+  <size>32</size>
+  <resetValue>0</resetValue>
+  <resetMask>0xFFFFFFFF</resetMask>
+  <peripherals>
+    <peripheral>
+      <name>UART0</name>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <register>
+          <name>CR</name>
+          <addressOffset>0x0</addressOffset>
+          <fields>
+            <field><name>EN</name><bitOffset>0</bitOffset><bitWidth>1</bitWidth></field>
+            <field>
+              <name>MODE</name><bitOffset>1</bitOffset><bitWidth>1</bitWidth>
+              <enumeratedValues>
+                <enumeratedValue><name>A</name><value>0</value></enumeratedValue>
+                <enumeratedValue><name>B</name><value>1</value></enumeratedValue>
+              </enumeratedValues>
+            </field>
+            <field>
+              <name>DIV</name><bitOffset>8</bitOffset><bitWidth>8</bitWidth>
+              <writeConstraint><range><minimum>1</minimum><maximum>200</maximum></range></writeConstraint>
+            </field>
+            <field><name>CH%s</name><dim>2</dim><dimIncrement>1</dimIncrement><bitOffset>16</bitOffset><bitWidth>1</bitWidth></field>
+            <field><name>SET_VALUE</name><bitOffset>20</bitOffset><bitWidth>1</bitWidth></field>
+            <field><name>FIELDS</name><bitOffset>21</bitOffset><bitWidth>1</bitWidth></field>
+          </fields>
+        </register>
+        <register>
+          <name>SR</name>
+          <addressOffset>0x4</addressOffset>
+          <fields>
+            <field><name>RXNE</name><bitOffset>0</bitOffset><bitWidth>1</bitWidth><access>read-only</access></field>
+            <field><name>ERR</name><bitOffset>1</bitOffset><bitWidth>1</bitWidth><modifiedWriteValues>oneToClear</modifiedWriteValues></field>
+          </fields>
+        </register>
+        <register><name>DR</name><addressOffset>0x8</addressOffset><size>16</size></register>
+      </registers>
+    </peripheral>
+    <peripheral derivedFrom="UART0"><name>UART1</name><baseAddress>0x40001000</baseAddress></peripheral>
+  </peripherals>
+</device>"#;
+    // name of the crate and changes of the default configuration
+    type Variant = (&'static str, fn(&mut Config));
+    let variants: [Variant; 12] = [
+        ("vcell", |_| {}),
+        ("ptr", |c| c.backend = Backend::Ptr),
+        ("sim", |c| c.backend = Backend::Sim),
+        ("transport", |c| c.backend = Backend::Transport),
+        ("split", |c| c.split = true),
+        ("portable", |c| c.atomics = true),
+        ("native", |c| {
+            c.atomics = true;
+            c.atomics_backend = AtomicsBackend::Native;
+        }),
+        ("critical-section", |c| {
+            c.atomics = true;
+            c.atomics_backend = AtomicsBackend::CriticalSection;
+        }),
+        ("auto", |c| {
+            c.atomics = true;
+            c.atomics_backend = AtomicsBackend::Auto;
+        }),
+        ("field-structs", |c| c.field_structs = true),
+        ("const-values", |c| c.const_values = true),
+        ("big-endian", |c| {
+            c.atomics = true;
+            c.settings.endian = Some(Endian::Big);
+        }),
+    ];
+    let root = std::env::temp_dir().join("svd2rust-generated-crates");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    for (name, f) in variants {
+        let mut config = Config {
+            make_crate: true,
+            target: Target::None,
+            ..Default::default()
+        };
+        f(&mut config);
+        let dir = root.join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        generate(svd, &config).unwrap().write_to(&dir).unwrap();
+        let output = std::process::Command::new(&cargo)
+            .args(["check", "--quiet", "--manifest-path"])
+            .arg(dir.join("Cargo.toml"))
+            .env("CARGO_TARGET_DIR", root.join("target"))
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "generated `{name}` crate doesn't compile:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+#[test]
+fn expanded_array_offsets() {
+    let svd = r#"
+<device>
+  <name>TEST</name>
+  <width>32</width>
+  <size>32</size>
+  <peripherals>
+    <peripheral>
+      <name>TIMER</name>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <register>
+          <dim>2</dim><dimIncrement>4</dimIncrement><dimIndex>A,B</dimIndex>
+          <name>CC%s</name>
+          <description>Compare %s</description>
+          <addressOffset>0x10</addressOffset>
+        </register>
+      </registers>
+    </peripheral>
+  </peripherals>
+</device>"#;
+    // with `keep_list` the array is expanded to a field per element, each accessor is
+    // documented with the offset of its element
+    let config = Config {
+        target: Target::None,
+        keep_list: true,
+        ..Default::default()
+    };
+    let generation = generate(svd, &config).unwrap();
+    let lib_rs = generation.lib_rs().unwrap();
+    assert!(lib_rs.contains("0x10 - Compare A"));
+    assert!(lib_rs.contains("0x14 - Compare B"));
+    // and the `ptr` backend addresses them at these offsets
+    let config = Config {
+        backend: Backend::Ptr,
+        ..config
+    };
+    let generation = generate(svd, &config).unwrap();
+    let lib_rs = generation.lib_rs().unwrap();
+    assert!(lib_rs.contains(".add(16)") && lib_rs.contains(".add(20)"));
+}
@@ -4,23 +4,25 @@ use log::{debug, error, info, warn};
 use svd2rust::config::{IdentFormatError, IdentFormats, IdentFormatsTheme};
 use svd2rust::util::IdentFormat;
 
-use std::io::Write;
 use std::process;
-use std::{fs::File, path::Path};
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result};
 use clap::{Arg, ArgAction, Command};
 
 use svd2rust::{
-    config::{Config, SourceType, Target},
-    generate, load_from,
-    util::{self, build_rs},
+    config::{Config, SourceType},
+    load_from, Generation,
 };
 
 fn parse_configs(app: Command) -> Result<Config> {
     use irx_config::parsers::{cmd, toml};
     use irx_config::ConfigBuilder;
-    let ident_formats = app.clone().get_matches();
+    let matches = app.clone().get_matches();
     let irxconfig = ConfigBuilder::default()
         .append_parser(cmd::ParserBuilder::new(app).exit_on_error(true).build()?)
         .append_parser(
@@ -42,7 +44,15 @@ fn parse_configs(app: Command) -> Result<Config> {
     idf.extend(config.ident_formats.drain());
     config.ident_formats = idf;
 
-    if let Some(ident_formats) = ident_formats.get_many::<String>("ident_format") {
+    if let Some(include) = matches.get_many::<String>("include") {
+        config.include.extend(include.map(Into::into));
+    }
+
+    if let Some(patches) = matches.get_many::<String>("patch") {
+        config.patches.extend(patches.map(Into::into));
+    }
+
+    if let Some(ident_formats) = matches.get_many::<String>("ident_format") {
         for fs in ident_formats {
             if let Some((n, fmt)) = fs.split_once(':') {
                 if let std::collections::hash_map::Entry::Occupied(mut e) =
@@ -107,6 +117,35 @@ fn run() -> Result<()> {
                 .action(ArgAction::Set)
                 .value_name("YAML_FILE"),
         )
+        .arg(
+            Arg::new("pack")
+                .long("pack")
+                .help("CMSIS-Pack directory or .pdsc file to take the input SVD from")
+                .action(ArgAction::Set)
+                .value_name("PATH"),
+        )
+        .arg(
+            Arg::new("pack_device")
+                .long("pack-device")
+                .alias("pack_device")
+                .help("Name of the device in the CMSIS-Pack")
+                .action(ArgAction::Set)
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("include")
+                .long("include")
+                .help("SVD file with additional peripherals merged into the input, can be repeated")
+                .action(ArgAction::Append)
+                .value_name("FILE"),
+        )
+        .arg(
+            Arg::new("patch")
+                .long("patch")
+                .help("YAML patch file applied to the device before generation, can be repeated")
+                .action(ArgAction::Append)
+                .value_name("YAML_FILE"),
+        )
         .arg(
             Arg::new("target")
                 .long("target")
@@ -120,6 +159,27 @@ fn run() -> Result<()> {
                 .action(ArgAction::SetTrue)
                 .help("Generate atomic register modification API"),
         )
+        .arg(
+            Arg::new("backend")
+                .long("backend")
+                .help("Implementation of register access: `vcell`, `ptr` for raw pointers, `transport` for devices behind a bus or `sim` for simulated registers")
+                .action(ArgAction::Set)
+                .value_name("BACKEND"),
+        )
+        .arg(
+            Arg::new("atomics_backend")
+                .long("atomics-backend")
+                .alias("atomics_backend")
+                .help("Implementation of atomic operations: `portable` for `portable-atomic`, `native`, `critical-section` or `auto` for native where the target supports it and `critical-section` elsewhere")
+                .action(ArgAction::Set)
+                .value_name("BACKEND"),
+        )
+        .arg(
+            Arg::new("bitband")
+                .long("bitband")
+                .action(ArgAction::SetTrue)
+                .help("Generate single-bit field writers through the Cortex-M bit-band alias region"),
+        )
         .arg(
             Arg::new("atomics_feature")
                 .long("atomics-feature")
@@ -207,6 +267,20 @@ Allowed cases are `unchanged` (''), `pascal` ('p'), `constant` ('c') and `snake`
                 .action(ArgAction::SetTrue)
                 .help("implement Debug for readable blocks and registers"),
         )
+        .arg(
+            Arg::new("field_structs")
+                .long("field-structs")
+                .alias("field_structs")
+                .action(ArgAction::SetTrue)
+                .help("Generate a plain struct of field values for each register"),
+        )
+        .arg(
+            Arg::new("const_values")
+                .long("const-values")
+                .alias("const_values")
+                .action(ArgAction::SetTrue)
+                .help("Generate `const fn` setters of fields for register values"),
+        )
         .arg(
             Arg::new("impl_debug_feature")
                 .long("impl-debug-feature")
@@ -231,6 +305,39 @@ Allowed cases are `unchanged` (''), `pascal` ('p'), `constant` ('c') and `snake`
                 .action(ArgAction::SetTrue)
                 .help("Create mod.rs instead of lib.rs, without inner attributes"),
         )
+        .arg(
+            Arg::new("make_crate")
+                .long("make-crate")
+                .alias("make_crate")
+                .action(ArgAction::SetTrue)
+                .help("Generate a complete crate with Cargo.toml and sources in src"),
+        )
+        .arg(
+            Arg::new("crate_name")
+                .long("crate-name")
+                .alias("crate_name")
+                .help("Package name used with `--make-crate`. Defaults to the device name")
+                .action(ArgAction::Set)
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .action(ArgAction::SetTrue)
+                .help("Check that files in the output directory are up to date, don't write anything"),
+        )
+        .arg(
+            Arg::new("provenance")
+                .long("provenance")
+                .action(ArgAction::SetTrue)
+                .help("Record svd2rust version, input hashes and configuration in generated code"),
+        )
+        .arg(
+            Arg::new("split")
+                .long("split")
+                .action(ArgAction::SetTrue)
+                .help("Write each module in a separate file instead of inlining it"),
+        )
         .arg(
             Arg::new("skip_crate_attributes")
                 .long("skip-crate-attributes")
@@ -276,6 +383,12 @@ Allowed cases are `unchanged` (''), `pascal` ('p'), `constant` ('c') and `snake`
 Useful for soft-cores where the peripheral address range isn't necessarily fixed.
 Ignore this option if you are not building your own FPGA based soft-cores."),
         )
+        .arg(
+            Arg::new("relocatable")
+                .long("relocatable")
+                .action(ArgAction::SetTrue)
+                .help("Store the register block pointer in peripherals to create them at runtime addresses"),
+        )
         .arg(
             Arg::new("log_level")
                 .long("log")
@@ -305,6 +418,25 @@ Ignore this option if you are not building your own FPGA based soft-cores."),
 
     debug!("Current svd2rust config: {config:#?}");
 
+    #[cfg(feature = "pack")]
+    let pack_device = match config.pack.as_ref() {
+        Some(pack) => {
+            let name = config
+                .pack_device
+                .as_deref()
+                .context("`--pack-device` is required with `--pack`")?;
+            let pack_device = svd2rust::pack::find(pack, name)?;
+            info!("Using {} from the pack", pack_device.svd.display());
+            config.input = Some(pack_device.svd.clone());
+            Some(pack_device)
+        }
+        None => None,
+    };
+    #[cfg(not(feature = "pack"))]
+    if config.pack.is_some() {
+        return Err(anyhow::anyhow!("Support for CMSIS-Packs is not available because svd2rust was compiled without the pack feature"));
+    }
+
     let input = &mut String::new();
     match config.input.as_ref() {
         Some(file) => {
@@ -345,73 +477,102 @@ Ignore this option if you are not building your own FPGA based soft-cores."),
     info!("Parsing device from SVD file");
     let device = load_from(input, &config)?;
 
-    let mut device_x = String::new();
     info!("Rendering device");
-    let items = generate::device::render(&device, &config, &mut device_x)
-        .with_context(|| "Error rendering device")?;
-
-    let filename = if config.make_mod { "mod.rs" } else { "lib.rs" };
-    let mut file = File::create(path.join(filename)).expect("Couldn't create output file");
-
-    let data = items.to_string().replace("] ", "]\n");
-    file.write_all(data.as_ref())
-        .expect("Could not write code to lib.rs");
-
-    if [
-        Target::CortexM,
-        Target::Msp430,
-        Target::XtensaLX,
-        Target::RISCV,
-    ]
-    .contains(&config.target)
-    {
-        writeln!(File::create(path.join("device.x"))?, "{device_x}")?;
-        writeln!(File::create(path.join("build.rs"))?, "{}", build_rs())?;
+    #[allow(unused_mut)]
+    let mut generation =
+        svd2rust::render(&device, input, &config).context("Error rendering device")?;
+    #[cfg(feature = "pack")]
+    if let Some(memory_x) = pack_device.and_then(|d| d.memory_x()) {
+        generation.files.insert("memory.x".into(), memory_x);
     }
-
-    if config.feature_group || config.feature_peripheral {
-        let feature_format = config.ident_formats.get("peripheral_feature").unwrap();
-        let mut features = Vec::new();
-        if config.feature_group {
-            features.extend(
-                util::group_names(&device, feature_format)
-                    .iter()
-                    .map(|s| format!("{s} = []\n")),
-            );
-            let add_groups: Vec<_> = util::group_names(&device, feature_format)
-                .iter()
-                .map(|s| format!("\"{s}\""))
-                .collect();
-            features.push(format!("all-groups = [{}]\n", add_groups.join(",")))
-        }
-        if config.feature_peripheral {
-            features.extend(
-                util::peripheral_names(&device, feature_format)
-                    .iter()
-                    .map(|s| format!("{s} = []\n")),
-            );
-            let add_peripherals: Vec<_> = util::peripheral_names(&device, feature_format)
-                .iter()
-                .map(|s| format!("\"{s}\""))
-                .collect();
-            features.push(format!(
-                "all-peripherals = [{}]\n",
-                add_peripherals.join(",")
-            ))
+    if config.check {
+        if !check(&generation, path) {
+            return Err(anyhow::anyhow!("Generated files are out of date"));
         }
-        write!(
-            File::create(path.join("features.toml"))?,
-            "# Below are the FEATURES generated by svd2rust base on groupName in SVD file.\n\
-            # Please copy them to Cargo.toml.\n\
-            [features]\n\
-            {}",
-            features.join("")
-        )?;
+        info!("Generated files are up to date");
+    } else {
+        generation.write_to(path)?;
     }
 
     Ok(())
 }
 
+/// Maximum number of diff lines printed for each file
+const MAX_DIFF_LINES: usize = 40;
+
+/// Compares generated files with files in `dir` and prints differences.
+/// Returns `true` if all the files are up to date.
+fn check(generation: &Generation, dir: &Path) -> bool {
+    let mut up_to_date = true;
+    for path in extra_modules(generation, dir) {
+        up_to_date = false;
+        error!("{} is not part of the generated files", path.display());
+    }
+    for (name, data) in &generation.files {
+        let path = dir.join(name);
+        match std::fs::read_to_string(&path) {
+            Ok(old) if old == *data => {}
+            Ok(old) => {
+                up_to_date = false;
+                error!("{} differs from generated file", path.display());
+                let diff = similar::TextDiff::from_lines(&old, data);
+                let diff = diff
+                    .unified_diff()
+                    .context_radius(3)
+                    .header(&path.display().to_string(), "generated")
+                    .to_string();
+                let lines: Vec<_> = diff.lines().collect();
+                for line in lines.iter().take(MAX_DIFF_LINES) {
+                    println!("{line}");
+                }
+                if lines.len() > MAX_DIFF_LINES {
+                    println!("... {} more lines", lines.len() - MAX_DIFF_LINES);
+                }
+            }
+            Err(e) => {
+                up_to_date = false;
+                error!("Cannot read {}: {e}", path.display());
+            }
+        }
+    }
+    up_to_date
+}
+
+/// Collects Rust files in the directories of the generated module tree at `dir` that are not
+/// generated, like modules of removed peripherals. Other directories of the crate, e.g. `tests`
+/// or `examples`, and the build script next to `lib.rs` are skipped.
+fn extra_modules(generation: &Generation, dir: &Path) -> Vec<PathBuf> {
+    let build_rs = Path::new("build.rs");
+    let modules: BTreeSet<&Path> = generation
+        .files
+        .keys()
+        .filter(|name| name.extension().is_some_and(|e| e == "rs") && *name != build_rs)
+        .filter_map(|name| name.parent())
+        .collect();
+    let mut extra = Vec::new();
+    for module in modules {
+        let Ok(entries) = std::fs::read_dir(dir.join(module)) else {
+            continue;
+        };
+        let mut names: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| module.join(e.file_name()))
+            .collect();
+        names.sort();
+        for name in names {
+            let path = dir.join(&name);
+            if name.extension().is_some_and(|e| e == "rs")
+                && name != build_rs
+                && !generation.files.contains_key(&name)
+                && path.is_file()
+            {
+                extra.push(path);
+            }
+        }
+    }
+    extra
+}
+
 fn setup_logging(log_level: &Option<String>) {
     // * Log at info by default.
     // * Allow users the option of setting complex logging filters using
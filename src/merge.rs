@@ -0,0 +1,121 @@
+//! Merging of devices described in several files
+
+use std::ops::Range;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::svd::{Device, MaybeArray, Peripheral, RegisterProperties};
+
+/// Adds peripherals of `other` to `device`.
+///
+/// Peripherals of `other` get its default register properties, so they don't depend on defaults
+/// of `device`. Fails if a peripheral with the same name already exists or if address ranges
+/// of peripherals from different devices overlap.
+pub fn merge(device: &mut Device, other: Device) -> Result<()> {
+    for p in &other.peripherals {
+        if device.peripherals.iter().any(|e| e.name == p.name) {
+            bail!("Peripheral `{}` is already defined", p.name);
+        }
+    }
+    for p in &other.peripherals {
+        for range in address_ranges(p, &other.peripherals)? {
+            for e in &device.peripherals {
+                if let Some(r) = address_ranges(e, &device.peripherals)?
+                    .into_iter()
+                    .find(|r| r.start < range.end && range.start < r.end)
+                {
+                    bail!(
+                        "Address range {:#x}..{:#x} of peripheral `{}` overlaps {:#x}..{:#x} of peripheral `{}`",
+                        range.start,
+                        range.end,
+                        p.name,
+                        r.start,
+                        r.end,
+                        e.name,
+                    );
+                }
+            }
+        }
+    }
+
+    if device.cpu.is_none() {
+        device.cpu = other.cpu;
+    }
+    let defaults = other.default_register_properties;
+    for mut p in other.peripherals {
+        inherit(&mut p.default_register_properties, &defaults);
+        device.peripherals.push(p);
+    }
+    Ok(())
+}
+
+fn inherit(props: &mut RegisterProperties, defaults: &RegisterProperties) {
+    props.size = props.size.or(defaults.size);
+    props.access = props.access.or(defaults.access);
+    props.protection = props.protection.or(defaults.protection);
+    props.reset_value = props.reset_value.or(defaults.reset_value);
+    props.reset_mask = props.reset_mask.or(defaults.reset_mask);
+}
+
+/// Address ranges occupied by the peripheral (all elements of the peripheral array).
+///
+/// Address blocks of the base peripheral are used for derived peripherals.
+/// A peripheral without address blocks occupies only its base address.
+/// Fails if a range doesn't fit in the 64-bit address space.
+fn address_ranges(p: &Peripheral, peripherals: &[Peripheral]) -> Result<Vec<Range<u64>>> {
+    let overflow = || anyhow!("Address range of peripheral `{}` overflows", p.name);
+    let blocks = p.address_block.as_ref().or_else(|| {
+        p.derived_from
+            .as_ref()
+            .and_then(|base| peripherals.iter().find(|b| &b.name == base))
+            .and_then(|b| b.address_block.as_ref())
+    });
+    let bases: Vec<u64> = match p {
+        MaybeArray::Single(info) => vec![info.base_address],
+        MaybeArray::Array(info, dim) => (0..u64::from(dim.dim))
+            .map(|i| {
+                i.checked_mul(u64::from(dim.dim_increment))
+                    .and_then(|offset| info.base_address.checked_add(offset))
+                    .ok_or_else(overflow)
+            })
+            .collect::<Result<_>>()?,
+    };
+    let mut ranges = Vec::new();
+    for base in bases {
+        match blocks {
+            Some(blocks) if !blocks.is_empty() => {
+                for b in blocks.iter().filter(|b| b.size > 0) {
+                    let start = base.checked_add(u64::from(b.offset)).ok_or_else(overflow)?;
+                    let end = start.checked_add(u64::from(b.size)).ok_or_else(overflow)?;
+                    ranges.push(start..end);
+                }
+            }
+            _ => ranges.push(base..base.checked_add(1).ok_or_else(overflow)?),
+        }
+    }
+    Ok(ranges)
+}
+
+#[test]
+fn merge_overlap() {
+    let device = |name: &str, base: &str| {
+        let svd = format!(
+            r#"<device><name>{name}</name><peripherals><peripheral>
+            <name>{name}</name><baseAddress>{base}</baseAddress>
+            <addressBlock><offset>0</offset><size>0x400</size><usage>registers</usage></addressBlock>
+            </peripheral></peripherals></device>"#
+        );
+        svd_parser::parse(&svd).unwrap()
+    };
+    let mut d = device("CORE", "0xE0000000");
+    merge(&mut d, device("UART", "0x40000000")).unwrap();
+    assert_eq!(d.peripherals.len(), 2);
+    assert!(merge(&mut d, device("UART", "0x50000000")).is_err());
+    assert!(merge(&mut d, device("SPI", "0x400003FC")).is_err());
+    merge(&mut d, device("SPI", "0x40000400")).unwrap();
+    let err = merge(&mut d, device("DMA", "0xFFFFFFFFFFFFFF00")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Address range of peripheral `DMA` overflows"
+    );
+}
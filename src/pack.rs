@@ -0,0 +1,303 @@
+//! Discovery of devices in CMSIS-Packs
+//!
+//! The pack description (`*.pdsc`) lists devices in a `family` / `subFamily` / `device` /
+//! `variant` hierarchy. Properties of a device are inherited from its parents: the nearest
+//! `debug` element with an `svd` attribute gives the SVD file and `memory` elements of all the
+//! levels give memory regions (a region with the same name on a lower level replaces the
+//! inherited one).
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use roxmltree::{Document, Node};
+
+/// Device found in a pack
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PackDevice {
+    /// Name of the device as written in the PDSC
+    pub name: String,
+    /// Path to the SVD file of the device
+    pub svd: PathBuf,
+    /// Memory regions of the device
+    pub memory: Vec<Memory>,
+}
+
+/// Memory region from a PDSC `memory` element
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Memory {
+    /// `name` (or legacy `id`) of the region
+    pub name: String,
+    /// Start address
+    pub start: u64,
+    /// Size in bytes
+    pub size: u64,
+    /// Access attribute, like `rx` or `rwx`
+    pub access: String,
+    /// Region is used for the boot code
+    pub startup: bool,
+    /// Region is used by default
+    pub default: bool,
+}
+
+impl Memory {
+    fn is_flash(&self) -> bool {
+        self.startup
+            || self.name.starts_with("IROM")
+            || (self.access.contains('x') && !self.access.contains('w'))
+    }
+
+    fn is_ram(&self) -> bool {
+        self.name.starts_with("IRAM") || self.access.contains('w')
+    }
+}
+
+/// Finds `device` (`Dname` or `Dvariant`, case insensitive) in the pack.
+///
+/// `pack` is an unpacked pack directory or a `.pdsc` file.
+pub fn find(pack: &Path, device: &str) -> Result<PackDevice> {
+    let pdscs = if pack.is_dir() {
+        let mut pdscs = Vec::new();
+        for entry in std::fs::read_dir(pack)
+            .with_context(|| format!("Cannot read pack directory {}", pack.display()))?
+        {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("pdsc"))
+            {
+                pdscs.push(path);
+            }
+        }
+        if pdscs.is_empty() {
+            bail!("No .pdsc file found in {}", pack.display());
+        }
+        pdscs.sort();
+        pdscs
+    } else {
+        vec![pack.to_path_buf()]
+    };
+
+    for pdsc in pdscs {
+        let text = std::fs::read_to_string(&pdsc)
+            .with_context(|| format!("Cannot read {}", pdsc.display()))?;
+        let found = find_in_pdsc(&text, device)
+            .with_context(|| format!("Error parsing {}", pdsc.display()))?;
+        if let Some(mut found) = found {
+            let dir = pdsc.parent().unwrap_or(Path::new(""));
+            found.svd = dir.join(&found.svd);
+            return Ok(found);
+        }
+    }
+    bail!(
+        "Device `{device}` is not found in the pack {}",
+        pack.display()
+    )
+}
+
+/// Finds `device` in the PDSC. The returned SVD path is relative to the PDSC file
+pub fn find_in_pdsc(pdsc: &str, device: &str) -> Result<Option<PackDevice>> {
+    let doc = Document::parse(pdsc)?;
+    let Some(devices) = doc
+        .root_element()
+        .children()
+        .find(|n| n.has_tag_name("devices"))
+    else {
+        return Ok(None);
+    };
+    let Some(node) = devices.descendants().find(|n| {
+        let name = match n.tag_name().name() {
+            "device" => n.attribute("Dname"),
+            "variant" => n.attribute("Dvariant"),
+            _ => None,
+        };
+        name.is_some_and(|name| name.eq_ignore_ascii_case(device))
+    }) else {
+        return Ok(None);
+    };
+
+    // from the device up to the family
+    let levels: Vec<Node> = node
+        .ancestors()
+        .take_while(|n| !n.has_tag_name("devices"))
+        .collect();
+
+    let svd = levels
+        .iter()
+        .flat_map(|level| level.children().filter(|n| n.has_tag_name("debug")))
+        .find_map(|debug| debug.attribute("svd"))
+        .ok_or_else(|| anyhow!("Device `{device}` has no SVD file"))?;
+
+    let mut memory: Vec<Memory> = Vec::new();
+    for level in levels.iter().rev() {
+        for node in level.children().filter(|n| n.has_tag_name("memory")) {
+            let region = self::memory(node)?;
+            match memory.iter_mut().find(|m| m.name == region.name) {
+                Some(m) => *m = region,
+                None => memory.push(region),
+            }
+        }
+    }
+
+    Ok(Some(PackDevice {
+        name: node
+            .attribute("Dvariant")
+            .or(node.attribute("Dname"))
+            .unwrap_or(device)
+            .into(),
+        svd: PathBuf::from(svd.replace('\\', "/")),
+        memory,
+    }))
+}
+
+fn memory(node: Node) -> Result<Memory> {
+    let name = node
+        .attribute("name")
+        .or(node.attribute("id"))
+        .ok_or_else(|| anyhow!("Memory region without name"))?;
+    let number = |attr: &str| -> Result<u64> {
+        let value = node
+            .attribute(attr)
+            .ok_or_else(|| anyhow!("Memory region `{name}` has no `{attr}`"))?;
+        let value = value.trim();
+        match value.strip_prefix("0x").or(value.strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => value.parse(),
+        }
+        .with_context(|| format!("Invalid `{attr}` of memory region `{name}`"))
+    };
+    let access = match node.attribute("access") {
+        Some(access) => access.into(),
+        // legacy ids: IROMx, IRAMx
+        None if name.starts_with("IROM") => "rx".into(),
+        None => "rwx".into(),
+    };
+    Ok(Memory {
+        name: name.into(),
+        start: number("start")?,
+        size: number("size")?,
+        access,
+        startup: node.attribute("startup") == Some("1"),
+        default: node.attribute("default") == Some("1"),
+    })
+}
+
+impl PackDevice {
+    /// Starter `memory.x` linker script in `cortex-m-rt` format.
+    ///
+    /// The startup region is called `FLASH` and the first writable default region is called `RAM`,
+    /// other regions keep their names, with a number appended if the name is already taken
+    /// (e.g. `FLASH1`). Returns `None` if there are no memory regions.
+    pub fn memory_x(&self) -> Option<String> {
+        let flash = self
+            .memory
+            .iter()
+            .position(|m| m.startup)
+            .or_else(|| self.memory.iter().position(Memory::is_flash))?;
+        let ram = self
+            .memory
+            .iter()
+            .position(|m| m.default && m.is_ram() && !m.startup)
+            .or_else(|| self.memory.iter().position(|m| m.is_ram() && !m.startup))?;
+
+        let mut out = format!(
+            "/* Memory regions of {} from the CMSIS-Pack */\nMEMORY\n{{\n",
+            self.name
+        );
+        let mut names = vec!["FLASH".to_string(), "RAM".to_string()];
+        for (i, m) in self.memory.iter().enumerate() {
+            let name = if i == flash {
+                "FLASH".into()
+            } else if i == ram {
+                "RAM".into()
+            } else {
+                let name = m
+                    .name
+                    .to_uppercase()
+                    .replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+                let name = std::iter::once(name.clone())
+                    .chain((1..).map(|n| format!("{name}{n}")))
+                    .find(|name| !names.contains(name))
+                    .unwrap();
+                names.push(name.clone());
+                name
+            };
+            let length = if m.size % 1024 == 0 {
+                format!("{}K", m.size / 1024)
+            } else {
+                format!("{:#x}", m.size)
+            };
+            out.push_str(&format!(
+                "  {name} : ORIGIN = 0x{:08X}, LENGTH = {length}\n",
+                m.start
+            ));
+        }
+        out.push_str("}\n");
+        Some(out)
+    }
+}
+
+#[test]
+fn find_device() {
+    let pdsc = r#"<?xml version="1.0" encoding="UTF-8"?>
+<package schemaVersion="1.7.2">
+  <vendor>Acme</vendor>
+  <name>ACME_DFP</name>
+  <devices>
+    <family Dfamily="ACME1" Dvendor="Acme:0">
+      <processor Dcore="Cortex-M4"/>
+      <debug svd="SVD\ACME1.svd"/>
+      <memory name="Flash" access="rx" start="0x08000000" size="0x40000" startup="1" default="1"/>
+      <memory name="SRAM" access="rwx" start="0x20000000" size="0x8000" default="1"/>
+      <subFamily DsubFamily="ACME1x">
+        <device Dname="ACME100">
+          <memory name="SRAM" access="rwx" start="0x20000000" size="0x10000" default="1"/>
+          <variant Dvariant="ACME100-QFN"/>
+        </device>
+      </subFamily>
+    </family>
+  </devices>
+</package>"#;
+    let mut device = find_in_pdsc(pdsc, "acme100-qfn").unwrap().unwrap();
+    assert_eq!(device.svd, PathBuf::from("SVD/ACME1.svd"));
+    assert_eq!(device.memory.len(), 2);
+    assert_eq!(device.memory[1].size, 0x10000);
+    assert_eq!(
+        device.memory_x().unwrap(),
+        "/* Memory regions of ACME100-QFN from the CMSIS-Pack */
+MEMORY
+{
+  FLASH : ORIGIN = 0x08000000, LENGTH = 256K
+  RAM : ORIGIN = 0x20000000, LENGTH = 64K
+}
+"
+    );
+    assert!(find_in_pdsc(pdsc, "ACME200").unwrap().is_none());
+
+    // extra regions with the names of the default ones are numbered
+    for (name, start) in [
+        ("flash", 0x0810_0000),
+        ("ram", 0x1000_0000),
+        ("Ram", 0x1001_0000),
+    ] {
+        device.memory.push(Memory {
+            name: name.into(),
+            start,
+            ..device.memory[1].clone()
+        });
+    }
+    assert_eq!(
+        device.memory_x().unwrap(),
+        "/* Memory regions of ACME100-QFN from the CMSIS-Pack */
+MEMORY
+{
+  FLASH : ORIGIN = 0x08000000, LENGTH = 256K
+  RAM : ORIGIN = 0x20000000, LENGTH = 64K
+  FLASH1 : ORIGIN = 0x08100000, LENGTH = 64K
+  RAM1 : ORIGIN = 0x10000000, LENGTH = 64K
+  RAM2 : ORIGIN = 0x10010000, LENGTH = 64K
+}
+"
+    );
+}
@@ -0,0 +1,821 @@
+//! svdtools-style YAML patches applied to the device before rendering
+//!
+//! A patch file is a YAML map. Keys starting with `_` are commands, other keys are glob patterns
+//! selecting peripherals (at the top level), registers (inside a peripheral) or fields
+//! (inside a register):
+//!
+//! ```yaml
+//! _include:
+//!   - common.yaml
+//! _delete:
+//!   - DBG*
+//! _modify:
+//!   name: STM32F0x1
+//!   USART1:
+//!     groupName: USART
+//! _add:
+//!   TIM20:
+//!     baseAddress: 0x40015000
+//!     description: General purpose timer
+//!     interrupts:
+//!       TIM20:
+//!         value: 77
+//! _derive:
+//!   USART2: USART1
+//!
+//! GPIO*:
+//!   _modify:
+//!     OTYPER:
+//!       access: read-write
+//!   _array:
+//!     AFR*: {}
+//!   MODER:
+//!     _modify:
+//!       MODER*:
+//!         description: Port mode
+//!     MODER*:
+//!       Input: [0, "Input mode"]
+//!       Output: [1, "General purpose output mode"]
+//!       _write:
+//!         Analog: [3, "Analog mode"]
+//! ```
+//!
+//! `_delete` of a peripheral removes matching registers and clusters, also inside of clusters.
+//! Paths in `_include` are relative to the file they are listed in. A file can't include itself,
+//! directly or through other files.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_yaml::{Mapping, Value};
+
+use crate::svd::{
+    self, Access, BitRange, DimElement, EnumeratedValue, EnumeratedValues, FieldInfo,
+    ModifiedWriteValues, PeripheralInfo, Register, RegisterCluster, RegisterInfo, Usage,
+    ValidateLevel,
+};
+use crate::util::matches_glob;
+
+/// Applies the patch files to the device in order
+pub fn apply(device: &mut svd::Device, patches: &[impl AsRef<Path>]) -> Result<()> {
+    for path in patches {
+        let path = path.as_ref();
+        apply_file(device, path, &mut Vec::new())
+            .with_context(|| format!("Can't apply patch {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// `stack` holds the files being applied, to detect include cycles
+fn apply_file(device: &mut svd::Device, path: &Path, stack: &mut Vec<PathBuf>) -> Result<()> {
+    let canonical = std::fs::canonicalize(path).context("could not read patch file")?;
+    if stack.contains(&canonical) {
+        bail!("patch file includes itself");
+    }
+    let file = std::fs::read_to_string(path).context("could not read patch file")?;
+    let patch: Mapping = serde_yaml::from_str(&file).context("could not parse patch file")?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    stack.push(canonical);
+    let result = process_device(device, &patch, dir, stack);
+    stack.pop();
+    result
+}
+
+fn process_device(
+    device: &mut svd::Device,
+    patch: &Mapping,
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<()> {
+    if let Some(includes) = patch.get("_include") {
+        for include in as_seq(includes)? {
+            apply_file(device, &dir.join(as_str(include)?), stack)
+                .with_context(|| format!("Can't include {}", as_str(include).unwrap()))?;
+        }
+    }
+
+    if let Some(delete) = patch.get("_delete") {
+        for pattern in as_seq(delete)? {
+            let pattern = as_str(pattern)?;
+            device
+                .peripherals
+                .retain(|p| !matches_glob(pattern, &p.name));
+        }
+    }
+
+    if let Some(modify) = patch.get("_modify") {
+        for (key, value) in as_map(modify)? {
+            let key = as_str(key)?;
+            match key {
+                "name" => device.name = as_string(value)?,
+                "description" => device.description = as_string(value)?,
+                "version" => device.version = as_string(value)?,
+                "vendor" => device.vendor = Some(as_string(value)?),
+                _ => {
+                    let modify = as_map(value)?;
+                    let mut found = false;
+                    for p in device.peripherals.iter_mut() {
+                        if matches_glob(key, &p.name) {
+                            modify_peripheral(p, modify)
+                                .with_context(|| format!("Can't modify peripheral {}", p.name))?;
+                            found = true;
+                        }
+                    }
+                    if !found {
+                        bail!("Could not find peripheral `{key}` to modify");
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(add) = patch.get("_add") {
+        for (name, spec) in as_map(add)? {
+            let name = as_str(name)?;
+            if device.peripherals.iter().any(|p| p.name == name) {
+                bail!("Peripheral `{name}` already exists");
+            }
+            let p = new_peripheral(name, as_map(spec)?)
+                .with_context(|| format!("Can't add peripheral {name}"))?;
+            device.peripherals.push(svd::Peripheral::Single(p));
+        }
+    }
+
+    if let Some(derive) = patch.get("_derive") {
+        for (name, spec) in as_map(derive)? {
+            let name = as_str(name)?;
+            let (base, spec) = derive_spec(spec)?;
+            if !device.peripherals.iter().any(|p| p.name == base) {
+                bail!("Could not find peripheral `{base}` to derive `{name}` from");
+            }
+            if let Some(p) = device.peripherals.iter_mut().find(|p| p.name == name) {
+                p.derived_from = Some(base.into());
+                p.registers = None;
+                if let Some(spec) = spec {
+                    modify_peripheral(p, spec)?;
+                }
+            } else {
+                let spec = spec.ok_or_else(|| {
+                    anyhow!("Peripheral `{name}` does not exist, `baseAddress` is required")
+                })?;
+                let mut p = new_peripheral(name, spec)
+                    .with_context(|| format!("Can't derive peripheral {name}"))?;
+                p.derived_from = Some(base.into());
+                device.peripherals.push(svd::Peripheral::Single(p));
+            }
+        }
+    }
+
+    for (key, value) in patch {
+        let key = as_str(key)?;
+        if key.starts_with('_') {
+            continue;
+        }
+        let patch = as_map(value)?;
+        let mut found = false;
+        for p in device.peripherals.iter_mut() {
+            if matches_glob(key, &p.name) {
+                process_peripheral(p, patch)
+                    .with_context(|| format!("Can't patch peripheral {}", p.name))?;
+                found = true;
+            }
+        }
+        if !found {
+            bail!("Could not find peripheral `{key}`");
+        }
+    }
+
+    Ok(())
+}
+
+fn process_peripheral(p: &mut PeripheralInfo, patch: &Mapping) -> Result<()> {
+    let registers = p.registers.get_or_insert_with(Vec::new);
+
+    if let Some(delete) = patch.get("_delete") {
+        for pattern in as_seq(delete)? {
+            let pattern = as_str(pattern)?;
+            delete_registers(registers, pattern);
+        }
+    }
+
+    if let Some(modify) = patch.get("_modify") {
+        for (key, value) in as_map(modify)? {
+            let key = as_str(key)?;
+            let modify = as_map(value)?;
+            let mut found = false;
+            for r in registers_mut(registers) {
+                if matches_glob(key, &r.name) {
+                    modify_register(r, modify)
+                        .with_context(|| format!("Can't modify register {}", r.name))?;
+                    found = true;
+                }
+            }
+            if !found {
+                bail!("Could not find register `{key}` to modify");
+            }
+        }
+    }
+
+    if let Some(add) = patch.get("_add") {
+        for (name, spec) in as_map(add)? {
+            let name = as_str(name)?;
+            if registers.iter().any(|rc| rc_name(rc) == name) {
+                bail!("Register `{name}` already exists");
+            }
+            let r = new_register(name, as_map(spec)?)
+                .with_context(|| format!("Can't add register {name}"))?;
+            registers.push(RegisterCluster::Register(Register::Single(r)));
+        }
+    }
+
+    if let Some(derive) = patch.get("_derive") {
+        for (name, spec) in as_map(derive)? {
+            let name = as_str(name)?;
+            let (base, spec) = derive_spec(spec)?;
+            let existing = registers_mut(registers).find(|r| r.name == name);
+            if let Some(r) = existing {
+                r.derived_from = Some(base.into());
+                r.fields = None;
+                if let Some(spec) = spec {
+                    modify_register(r, spec)?;
+                }
+            } else {
+                let spec = spec.ok_or_else(|| {
+                    anyhow!("Register `{name}` does not exist, `addressOffset` is required")
+                })?;
+                let mut r = new_register(name, spec)
+                    .with_context(|| format!("Can't derive register {name}"))?;
+                r.derived_from = Some(base.into());
+                registers.push(RegisterCluster::Register(Register::Single(r)));
+            }
+        }
+    }
+
+    for (key, value) in patch {
+        let key = as_str(key)?;
+        if key.starts_with('_') {
+            continue;
+        }
+        let patch = as_map(value)?;
+        let mut found = false;
+        for r in registers_mut(registers) {
+            if matches_glob(key, &r.name) {
+                process_register(r, patch)
+                    .with_context(|| format!("Can't patch register {}", r.name))?;
+                found = true;
+            }
+        }
+        if !found {
+            bail!("Could not find register `{key}`");
+        }
+    }
+
+    if let Some(array) = patch.get("_array") {
+        for (pattern, spec) in as_map(array)? {
+            let pattern = as_str(pattern)?;
+            collect_in_array(registers, pattern, as_map(spec)?)
+                .with_context(|| format!("Can't collect registers `{pattern}` in array"))?;
+        }
+    }
+
+    if registers.is_empty() {
+        p.registers = None;
+    }
+
+    Ok(())
+}
+
+fn process_register(r: &mut RegisterInfo, patch: &Mapping) -> Result<()> {
+    let fields = r.fields.get_or_insert_with(Vec::new);
+
+    if let Some(delete) = patch.get("_delete") {
+        for pattern in as_seq(delete)? {
+            let pattern = as_str(pattern)?;
+            fields.retain(|f| !matches_glob(pattern, &f.name));
+        }
+    }
+
+    if let Some(modify) = patch.get("_modify") {
+        for (key, value) in as_map(modify)? {
+            let key = as_str(key)?;
+            let modify = as_map(value)?;
+            let mut found = false;
+            for f in fields.iter_mut() {
+                if matches_glob(key, &f.name) {
+                    modify_field(f, modify)
+                        .with_context(|| format!("Can't modify field {}", f.name))?;
+                    found = true;
+                }
+            }
+            if !found {
+                bail!("Could not find field `{key}` to modify");
+            }
+        }
+    }
+
+    if let Some(add) = patch.get("_add") {
+        for (name, spec) in as_map(add)? {
+            let name = as_str(name)?;
+            if fields.iter().any(|f| f.name == name) {
+                bail!("Field `{name}` already exists");
+            }
+            let f = new_field(name, as_map(spec)?)
+                .with_context(|| format!("Can't add field {name}"))?;
+            fields.push(svd::Field::Single(f));
+        }
+    }
+
+    for (key, value) in patch {
+        let key = as_str(key)?;
+        if key.starts_with('_') {
+            continue;
+        }
+        let patch = as_map(value)?;
+        let mut found = false;
+        for f in fields.iter_mut() {
+            if matches_glob(key, &f.name) {
+                add_enums(f, patch)
+                    .with_context(|| format!("Can't add enumerated values to field {}", f.name))?;
+                found = true;
+            }
+        }
+        if !found {
+            bail!("Could not find field `{key}`");
+        }
+    }
+
+    if fields.is_empty() {
+        r.fields = None;
+    }
+
+    Ok(())
+}
+
+/// Replaces enumerated values of the field with the same usage
+fn add_enums(f: &mut FieldInfo, patch: &Mapping) -> Result<()> {
+    let mut add = |usage: Option<Usage>, evs: EnumeratedValues| {
+        f.enumerated_values
+            .retain(|e| e.usage.unwrap_or(Usage::ReadWrite) != usage.unwrap_or(Usage::ReadWrite));
+        f.enumerated_values.push(evs);
+    };
+
+    if let Some(path) = patch.get("_derivedFrom") {
+        let mut evs = EnumeratedValues::builder()
+            .values(Vec::new())
+            .build(ValidateLevel::Disabled)?;
+        evs.derived_from = Some(as_string(path)?);
+        add(None, evs);
+        return Ok(());
+    }
+
+    if patch
+        .keys()
+        .any(|k| !matches!(k.as_str(), Some("_read" | "_write")))
+    {
+        add(None, new_enums(patch, None)?);
+    }
+    if let Some(read) = patch.get("_read") {
+        add(
+            Some(Usage::Read),
+            new_enums(as_map(read)?, Some(Usage::Read))?,
+        );
+    }
+    if let Some(write) = patch.get("_write") {
+        add(
+            Some(Usage::Write),
+            new_enums(as_map(write)?, Some(Usage::Write))?,
+        );
+    }
+    Ok(())
+}
+
+fn new_enums(variants: &Mapping, usage: Option<Usage>) -> Result<EnumeratedValues> {
+    let mut values = Vec::new();
+    for (name, value) in variants {
+        let name = as_str(name)?;
+        if name.starts_with('_') {
+            continue;
+        }
+        let seq = as_seq(value)?;
+        let (value, description) = match seq.as_slice() {
+            [value, description] => (value, Some(as_string(description)?)),
+            [value] => (value, None),
+            _ => bail!("Variant `{name}` must be `[value, description]`"),
+        };
+        let mut ev = EnumeratedValue::builder()
+            .name(name.into())
+            .build(ValidateLevel::Disabled)?;
+        ev.value = Some(as_u64(value)?);
+        ev.description = description;
+        values.push(ev);
+    }
+    let mut evs = EnumeratedValues::builder()
+        .values(values)
+        .build(ValidateLevel::Disabled)?;
+    evs.usage = usage;
+    Ok(evs)
+}
+
+/// Collects single registers matching `pattern` into a register array.
+///
+/// The array is named after `pattern` with `*` replaced by `%s` unless `name` is specified.
+fn collect_in_array(
+    registers: &mut Vec<RegisterCluster>,
+    pattern: &str,
+    spec: &Mapping,
+) -> Result<()> {
+    let mut matched: Vec<(usize, &RegisterInfo)> = registers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, rc)| match rc {
+            RegisterCluster::Register(Register::Single(r)) if matches_glob(pattern, &r.name) => {
+                Some((i, r))
+            }
+            _ => None,
+        })
+        .collect();
+    if matched.is_empty() {
+        bail!("No registers match");
+    }
+    matched.sort_by_key(|(_, r)| r.address_offset);
+
+    let name = match spec.get("name") {
+        Some(name) => as_string(name)?,
+        None => pattern.replacen('*', "%s", 1),
+    };
+    let (prefix, suffix) = name
+        .split_once("%s")
+        .ok_or_else(|| anyhow!("Array name `{name}` must contain `%s`"))?;
+
+    let first = matched[0].1;
+    let increment = matched
+        .get(1)
+        .map(|(_, r)| r.address_offset - first.address_offset)
+        .unwrap_or_default();
+    if matched.len() > 1 && increment == 0 {
+        bail!(
+            "`{}` and `{}` have the same address",
+            first.name,
+            matched[1].1.name
+        );
+    }
+    let mut indexes = Vec::with_capacity(matched.len());
+    for (n, (_, r)) in matched.iter().enumerate() {
+        let offset = u32::try_from(n)
+            .ok()
+            .and_then(|n| n.checked_mul(increment))
+            .and_then(|o| first.address_offset.checked_add(o));
+        if offset != Some(r.address_offset) {
+            bail!("Registers are not evenly spaced");
+        }
+        if r.fields != first.fields || r.properties != first.properties {
+            bail!("`{}` differs from `{}`", r.name, first.name);
+        }
+        let index = r
+            .name
+            .strip_prefix(prefix)
+            .and_then(|s| s.strip_suffix(suffix))
+            .ok_or_else(|| anyhow!("`{}` does not match `{name}`", r.name))?;
+        indexes.push(index.to_string());
+    }
+
+    let mut info = first.clone();
+    info.name = name;
+    if let Some(description) = spec.get("description") {
+        info.description = Some(as_string(description)?);
+    }
+    let dim = DimElement::builder()
+        .dim(matched.len() as u32)
+        .dim_increment(increment)
+        .dim_index(Some(indexes))
+        .build(ValidateLevel::Disabled)?;
+
+    let position = matched.iter().map(|(i, _)| *i).min().unwrap();
+    let mut remove: Vec<_> = matched.iter().map(|(i, _)| *i).collect();
+    remove.sort_unstable();
+    for i in remove.into_iter().rev() {
+        registers.remove(i);
+    }
+    registers.insert(
+        position,
+        RegisterCluster::Register(Register::Array(info, dim)),
+    );
+    Ok(())
+}
+
+fn modify_peripheral(p: &mut PeripheralInfo, modify: &Mapping) -> Result<()> {
+    for (key, value) in modify {
+        match as_str(key)? {
+            "name" => p.name = as_string(value)?,
+            "displayName" => p.display_name = Some(as_string(value)?),
+            "description" => p.description = Some(as_string(value)?),
+            "groupName" => p.group_name = Some(as_string(value)?),
+            "baseAddress" => p.base_address = as_u64(value)?,
+            "derivedFrom" => p.derived_from = Some(as_string(value)?),
+            "interrupts" => {
+                for (name, spec) in as_map(value)? {
+                    let name = as_str(name)?;
+                    let spec = as_map(spec)?;
+                    let value = as_u32(get(spec, "value")?)?;
+                    let mut interrupt = svd::Interrupt::builder()
+                        .name(name.into())
+                        .value(value)
+                        .build(ValidateLevel::Disabled)?;
+                    interrupt.description = spec.get("description").map(as_string).transpose()?;
+                    p.interrupt.retain(|i| i.name != name);
+                    p.interrupt.push(interrupt);
+                }
+            }
+            "registers" => {
+                for (name, spec) in as_map(value)? {
+                    let r = new_register(as_str(name)?, as_map(spec)?)?;
+                    p.registers
+                        .get_or_insert_with(Vec::new)
+                        .push(RegisterCluster::Register(Register::Single(r)));
+                }
+            }
+            key => bail!("Unknown peripheral property `{key}`"),
+        }
+    }
+    Ok(())
+}
+
+fn modify_register(r: &mut RegisterInfo, modify: &Mapping) -> Result<()> {
+    for (key, value) in modify {
+        match as_str(key)? {
+            "name" => r.name = as_string(value)?,
+            "displayName" => r.display_name = Some(as_string(value)?),
+            "description" => r.description = Some(as_string(value)?),
+            "alternateGroup" => r.alternate_group = Some(as_string(value)?),
+            "alternateRegister" => r.alternate_register = Some(as_string(value)?),
+            "addressOffset" => r.address_offset = as_u32(value)?,
+            "size" => r.properties.size = Some(as_u32(value)?),
+            "access" => r.properties.access = Some(as_access(value)?),
+            "resetValue" => r.properties.reset_value = Some(as_u64(value)?),
+            "resetMask" => r.properties.reset_mask = Some(as_u64(value)?),
+            "modifiedWriteValues" => r.modified_write_values = Some(as_mwv(value)?),
+            "derivedFrom" => r.derived_from = Some(as_string(value)?),
+            "fields" => {
+                for (name, spec) in as_map(value)? {
+                    let f = new_field(as_str(name)?, as_map(spec)?)?;
+                    r.fields
+                        .get_or_insert_with(Vec::new)
+                        .push(svd::Field::Single(f));
+                }
+            }
+            key => bail!("Unknown register property `{key}`"),
+        }
+    }
+    Ok(())
+}
+
+fn modify_field(f: &mut FieldInfo, modify: &Mapping) -> Result<()> {
+    for (key, value) in modify {
+        match as_str(key)? {
+            "name" => f.name = as_string(value)?,
+            "description" => f.description = Some(as_string(value)?),
+            "bitOffset" => {
+                f.bit_range = BitRange::from_offset_width(as_u32(value)?, f.bit_range.width)
+            }
+            "bitWidth" => {
+                f.bit_range = BitRange::from_offset_width(f.bit_range.offset, as_u32(value)?)
+            }
+            "access" => f.access = Some(as_access(value)?),
+            "modifiedWriteValues" => f.modified_write_values = Some(as_mwv(value)?),
+            "derivedFrom" => f.derived_from = Some(as_string(value)?),
+            key => bail!("Unknown field property `{key}`"),
+        }
+    }
+    Ok(())
+}
+
+fn new_peripheral(name: &str, spec: &Mapping) -> Result<PeripheralInfo> {
+    let base_address = as_u64(get(spec, "baseAddress")?)?;
+    let mut p = PeripheralInfo::builder()
+        .name(name.into())
+        .base_address(base_address)
+        .build(ValidateLevel::Disabled)?;
+    let mut spec = spec.clone();
+    spec.remove("baseAddress");
+    modify_peripheral(&mut p, &spec)?;
+    Ok(p)
+}
+
+fn new_register(name: &str, spec: &Mapping) -> Result<RegisterInfo> {
+    let address_offset = as_u32(get(spec, "addressOffset")?)?;
+    let mut r = RegisterInfo::builder()
+        .name(name.into())
+        .address_offset(address_offset)
+        .build(ValidateLevel::Disabled)?;
+    let mut spec = spec.clone();
+    spec.remove("addressOffset");
+    modify_register(&mut r, &spec)?;
+    Ok(r)
+}
+
+fn new_field(name: &str, spec: &Mapping) -> Result<FieldInfo> {
+    let offset = as_u32(get(spec, "bitOffset")?)?;
+    let width = as_u32(get(spec, "bitWidth")?)?;
+    let mut f = FieldInfo::builder()
+        .name(name.into())
+        .bit_range(BitRange::from_offset_width(offset, width))
+        .build(ValidateLevel::Disabled)?;
+    modify_field(&mut f, spec)?;
+    Ok(f)
+}
+
+/// `_derive` entry is either the name of the base element or a map with `derivedFrom`
+/// and properties of the element
+fn derive_spec(spec: &Value) -> Result<(&str, Option<&Mapping>)> {
+    match spec {
+        Value::String(base) => Ok((base, None)),
+        Value::Mapping(spec) => {
+            let base = as_str(get(spec, "derivedFrom")?)?;
+            Ok((base, Some(spec)))
+        }
+        _ => bail!("Expected name of the base element or a map with `derivedFrom`"),
+    }
+}
+
+/// Iterates over all registers of the peripheral including registers in clusters
+fn registers_mut(
+    registers: &mut [RegisterCluster],
+) -> Box<dyn Iterator<Item = &mut RegisterInfo> + '_> {
+    Box::new(registers.iter_mut().flat_map(|rc| match rc {
+        RegisterCluster::Register(r) => {
+            Box::new(std::iter::once(&mut **r)) as Box<dyn Iterator<Item = _>>
+        }
+        RegisterCluster::Cluster(c) => registers_mut(&mut c.children),
+    }))
+}
+
+/// Removes registers and clusters matching `pattern`, also inside of clusters
+fn delete_registers(registers: &mut Vec<RegisterCluster>, pattern: &str) {
+    registers.retain(|rc| !matches_glob(pattern, rc_name(rc)));
+    for rc in registers {
+        if let RegisterCluster::Cluster(c) = rc {
+            delete_registers(&mut c.children, pattern);
+        }
+    }
+}
+
+fn rc_name(rc: &RegisterCluster) -> &str {
+    match rc {
+        RegisterCluster::Register(r) => &r.name,
+        RegisterCluster::Cluster(c) => &c.name,
+    }
+}
+
+fn get<'a>(map: &'a Mapping, key: &str) -> Result<&'a Value> {
+    map.get(key).ok_or_else(|| anyhow!("`{key}` is required"))
+}
+
+fn as_map(value: &Value) -> Result<&Mapping> {
+    value
+        .as_mapping()
+        .ok_or_else(|| anyhow!("Expected map, found {value:?}"))
+}
+
+fn as_seq(value: &Value) -> Result<Vec<&Value>> {
+    match value {
+        Value::Sequence(seq) => Ok(seq.iter().collect()),
+        Value::String(_) => Ok(vec![value]),
+        _ => bail!("Expected list, found {value:?}"),
+    }
+}
+
+fn as_str(value: &Value) -> Result<&str> {
+    value
+        .as_str()
+        .ok_or_else(|| anyhow!("Expected string, found {value:?}"))
+}
+
+fn as_string(value: &Value) -> Result<String> {
+    as_str(value).map(Into::into)
+}
+
+fn as_u64(value: &Value) -> Result<u64> {
+    match value {
+        Value::Number(n) => n
+            .as_u64()
+            .ok_or_else(|| anyhow!("{n} is not a valid unsigned integer")),
+        Value::String(s) => parse_u64(s).ok_or_else(|| anyhow!("Can't parse `{s}` as integer")),
+        _ => bail!("Expected integer, found {value:?}"),
+    }
+}
+
+fn as_u32(value: &Value) -> Result<u32> {
+    let n = as_u64(value)?;
+    u32::try_from(n).map_err(|_| anyhow!("{n} doesn't fit in 32 bits"))
+}
+
+fn parse_u64(s: &str) -> Option<u64> {
+    let s = s.trim().replace('_', "");
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = s.strip_prefix("0b").or_else(|| s.strip_prefix("#")) {
+        u64::from_str_radix(bin, 2).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+fn as_access(value: &Value) -> Result<Access> {
+    let s = as_str(value)?;
+    Access::parse_str(s).ok_or_else(|| anyhow!("Unknown access `{s}`"))
+}
+
+fn as_mwv(value: &Value) -> Result<ModifiedWriteValues> {
+    let s = as_str(value)?;
+    ModifiedWriteValues::parse_str(s).ok_or_else(|| anyhow!("Unknown modifiedWriteValues `{s}`"))
+}
+
+#[test]
+fn patch_device() {
+    let svd = r#"
+<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral>
+      <name>DMA</name>
+      <baseAddress>0x40000000</baseAddress>
+      <registers>
+        <register><name>CH0</name><addressOffset>0x0</addressOffset><size>32</size></register>
+        <register><name>CH1</name><addressOffset>0x4</addressOffset><size>32</size></register>
+        <register><name>CH2</name><addressOffset>0x8</addressOffset><size>32</size></register>
+        <register>
+          <name>CR</name><addressOffset>0x10</addressOffset><size>32</size>
+          <fields><field><name>MODE</name><bitOffset>0</bitOffset><bitWidth>2</bitWidth></field></fields>
+        </register>
+        <cluster>
+          <name>CFG</name><addressOffset>0x20</addressOffset>
+          <register><name>SEL</name><addressOffset>0x0</addressOffset><size>32</size></register>
+          <register><name>DBGSEL</name><addressOffset>0x4</addressOffset><size>32</size></register>
+        </cluster>
+        <cluster>
+          <name>DBGCFG</name><addressOffset>0x30</addressOffset>
+          <register><name>TRACE</name><addressOffset>0x0</addressOffset><size>32</size></register>
+        </cluster>
+      </registers>
+    </peripheral>
+    <peripheral>
+      <name>DBG</name>
+      <baseAddress>0x50000000</baseAddress>
+    </peripheral>
+  </peripherals>
+</device>"#;
+    let mut device = svd_parser::parse(svd).unwrap();
+    let patch: Mapping = serde_yaml::from_str(
+        r#"
+_delete: [DBG]
+_derive:
+  DMA2:
+    derivedFrom: DMA
+    baseAddress: 0x40001000
+DMA:
+  _delete: [DBG*]
+  _modify:
+    CR:
+      access: read-write
+  _array:
+    CH*: {}
+  CR:
+    MODE:
+      Off: [0, "Disabled"]
+      On: [1, "Enabled"]
+"#,
+    )
+    .unwrap();
+    process_device(&mut device, &patch, Path::new(""), &mut Vec::new()).unwrap();
+
+    let names: Vec<_> = device.peripherals.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["DMA", "DMA2"]);
+    let registers = device.peripherals[0].registers.as_ref().unwrap();
+    assert!(
+        matches!(&registers[0], RegisterCluster::Register(Register::Array(r, dim))
+        if r.name == "CH%s" && dim.dim == 3 && dim.dim_increment == 4)
+    );
+    let RegisterCluster::Register(cr) = &registers[1] else {
+        panic!("CR is not a register")
+    };
+    assert_eq!(cr.properties.access, Some(Access::ReadWrite));
+    let mode = &cr.fields.as_ref().unwrap()[0];
+    assert_eq!(mode.enumerated_values[0].values.len(), 2);
+    let [RegisterCluster::Cluster(cfg)] = &registers[2..] else {
+        panic!("DBGCFG is not deleted")
+    };
+    let names: Vec<_> = cfg.children.iter().map(rc_name).collect();
+    assert_eq!(names, ["SEL"]);
+
+    // registers at the same address can't be collected in an array
+    let mut registers = device.peripherals[0].registers.clone().unwrap();
+    let RegisterCluster::Cluster(cfg) = &mut registers[2] else {
+        unreachable!()
+    };
+    let mut sel = cfg.children[0].clone();
+    if let RegisterCluster::Register(r) = &mut sel {
+        r.name = "SEL1".into();
+    }
+    cfg.children.push(sel);
+    assert!(collect_in_array(&mut cfg.children, "SEL*", &Mapping::new()).is_err());
+}
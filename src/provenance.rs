@@ -0,0 +1,135 @@
+//! Record of what the code was generated from
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use proc_macro2::TokenStream;
+use quote::quote;
+use sha2::{Digest, Sha256};
+
+use crate::Config;
+
+/// svd2rust version, input hashes and effective configuration of a generation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    /// Version of svd2rust with commit information
+    pub version: String,
+    /// SHA-256 of the input SVD
+    pub svd_sha256: String,
+    /// SHA-256 of the settings file, if any
+    pub settings_sha256: Option<String>,
+    /// SHA-256 of the included files, in order
+    pub include_sha256: Vec<String>,
+    /// SHA-256 of the patch files, in order
+    pub patches_sha256: Vec<String>,
+    /// Effective configuration, without options that don't change the generated code
+    pub config: serde_json::Value,
+}
+
+/// Options which select input and output files or control the run, they are recorded as hashes
+/// of the input files or not at all
+const RUNTIME_OPTIONS: [&str; 9] = [
+    "check",
+    "output_dir",
+    "input",
+    "pack",
+    "log_level",
+    "include",
+    "patches",
+    "settings_file",
+    "provenance",
+];
+
+impl Provenance {
+    pub fn new(input: &str, config: &Config) -> Result<Self> {
+        let settings_sha256 = config
+            .settings_file
+            .as_ref()
+            .map(|settings| file_sha256(settings))
+            .transpose()?;
+        let include_sha256 = config
+            .include
+            .iter()
+            .map(|path| file_sha256(path))
+            .collect::<Result<_>>()?;
+        let patches_sha256 = config
+            .patches
+            .iter()
+            .map(|path| file_sha256(path))
+            .collect::<Result<_>>()?;
+        let mut config = serde_json::to_value(config).context("could not serialize config")?;
+        if let Some(options) = config.as_object_mut() {
+            for option in RUNTIME_OPTIONS {
+                options.remove(option);
+            }
+        }
+        Ok(Self {
+            version: format!(
+                "{}{}",
+                env!("CARGO_PKG_VERSION"),
+                include_str!(concat!(env!("OUT_DIR"), "/commit-info.txt"))
+            ),
+            svd_sha256: sha256(input.as_bytes()),
+            settings_sha256,
+            include_sha256,
+            patches_sha256,
+            config,
+        })
+    }
+
+    /// Content of the `svd2rust.json` manifest
+    pub fn to_json(&self) -> Result<String> {
+        let manifest = serde_json::json!({
+            "svd2rust_version": self.version,
+            "svd_sha256": self.svd_sha256,
+            "settings_sha256": self.settings_sha256,
+            "include_sha256": self.include_sha256,
+            "patches_sha256": self.patches_sha256,
+            "config": self.config,
+        });
+        Ok(serde_json::to_string_pretty(&manifest)? + "\n")
+    }
+
+    /// `provenance` module of the generated crate
+    pub fn render(&self) -> TokenStream {
+        let version = &self.version;
+        let svd_sha256 = &self.svd_sha256;
+        let settings_sha256 = match self.settings_sha256.as_ref() {
+            Some(hash) => quote!(Some(#hash)),
+            None => quote!(None),
+        };
+        let include_sha256 = &self.include_sha256;
+        let patches_sha256 = &self.patches_sha256;
+        let config = self.config.to_string();
+        quote! {
+            ///Information about generation of this crate
+            pub mod provenance {
+                ///Version of svd2rust the crate was generated with
+                pub const SVD2RUST_VERSION: &str = #version;
+                ///SHA-256 of the input SVD file
+                pub const SVD_SHA256: &str = #svd_sha256;
+                ///SHA-256 of the settings file
+                pub const SETTINGS_SHA256: Option<&str> = #settings_sha256;
+                ///SHA-256 of the included files
+                pub const INCLUDE_SHA256: &[&str] = &[#(#include_sha256),*];
+                ///SHA-256 of the patch files
+                pub const PATCHES_SHA256: &[&str] = &[#(#patches_sha256),*];
+                ///Effective svd2rust configuration in JSON
+                pub const CONFIG: &str = #config;
+            }
+        }
+    }
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    std::fs::read(path)
+        .with_context(|| format!("could not read {}", path.display()))
+        .map(|data| sha256(&data))
+}
+
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
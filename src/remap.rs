@@ -0,0 +1,131 @@
+//! Base address overrides and extra instances of peripherals from settings
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::config::Settings;
+use crate::svd::{Device, MaybeArray, Peripheral};
+use crate::util::matches_glob;
+
+/// Moves peripherals and adds extra instances as peripherals derived from the original one
+pub fn apply(device: &mut Device, settings: &Settings) -> Result<()> {
+    let mut instances = Vec::new();
+    for (pattern, ps) in &settings.peripherals {
+        let matched: Vec<usize> = device
+            .peripherals
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| matches_glob(pattern, &p.name))
+            .map(|(i, _)| i)
+            .collect();
+        match matched.len() {
+            0 => bail!("No peripheral matches `{pattern}` from settings"),
+            1 => {}
+            _ if ps.base_address.is_some() || !ps.instances.is_empty() => {
+                bail!("`{pattern}` matches several peripherals, it can't have `base_address` or `instances`")
+            }
+            _ => {}
+        }
+        if !ps.instances.is_empty() {
+            instances.push((matched[0], &ps.instances));
+        }
+    }
+
+    for p in &mut device.peripherals {
+        let ps = settings.peripheral_settings(&p.name);
+        // an absolute base address wins over offsets of less specific keys
+        match (ps.base_address, ps.offset) {
+            (Some(base_address), _) => p.base_address = base_address,
+            (None, Some(offset)) => {
+                p.base_address = p
+                    .base_address
+                    .checked_add_signed(offset)
+                    .ok_or_else(|| anyhow!("Base address of `{}` overflows", p.name))?;
+            }
+            (None, None) => {}
+        }
+    }
+
+    for (i, names) in instances {
+        for (name, &base_address) in names {
+            let p = &device.peripherals[i];
+            if matches!(p, MaybeArray::Array(..)) {
+                bail!("Can't add instances of peripheral array `{}`", p.name);
+            }
+            if device.peripherals.iter().any(|p| &p.name == name) {
+                bail!("Peripheral `{name}` already exists");
+            }
+            let mut instance = (**p).clone();
+            // derive from the peripheral that has registers
+            instance.derived_from = Some(p.derived_from.clone().unwrap_or_else(|| p.name.clone()));
+            instance.name = name.clone();
+            instance.base_address = base_address;
+            instance.registers = None;
+            instance.interrupt = Vec::new();
+            device.peripherals.push(Peripheral::Single(instance));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn remap_instances() {
+    let svd = r#"
+<device>
+  <name>TEST</name>
+  <peripherals>
+    <peripheral><name>UART0</name><baseAddress>0x40000000</baseAddress></peripheral>
+    <peripheral><name>UART1</name><baseAddress>0x40001000</baseAddress></peripheral>
+    <peripheral><name>SPI0</name><baseAddress>0x40002000</baseAddress></peripheral>
+    <peripheral><name>I2C0</name><baseAddress>0x40003000</baseAddress></peripheral>
+  </peripherals>
+</device>"#;
+    use crate::config::PeripheralSettings;
+    use std::collections::BTreeMap;
+
+    let mut device = svd_parser::parse(svd).unwrap();
+    let peripherals = BTreeMap::from([
+        (
+            "*".to_string(),
+            PeripheralSettings {
+                offset: Some(0x10),
+                ..Default::default()
+            },
+        ),
+        (
+            "SPI0".to_string(),
+            PeripheralSettings {
+                base_address: Some(0x5000_0000),
+                instances: BTreeMap::from([("SPI1".to_string(), 0x5000_1000)]),
+                ..Default::default()
+            },
+        ),
+        (
+            "UART*".to_string(),
+            PeripheralSettings {
+                offset: Some(-0x1000_0000),
+                ..Default::default()
+            },
+        ),
+    ]);
+    let settings = Settings {
+        peripherals,
+        ..Default::default()
+    };
+    apply(&mut device, &settings).unwrap();
+    let bases: Vec<_> = device
+        .peripherals
+        .iter()
+        .map(|p| (p.name.as_str(), p.base_address))
+        .collect();
+    assert_eq!(
+        bases,
+        [
+            ("UART0", 0x3000_0000),
+            ("UART1", 0x3000_1000),
+            ("SPI0", 0x5000_0000),
+            ("I2C0", 0x4000_3010),
+            ("SPI1", 0x5000_1000)
+        ]
+    );
+    assert_eq!(device.peripherals[4].derived_from.as_deref(), Some("SPI0"));
+}
@@ -0,0 +1,77 @@
+//! Splitting of the generated crate into a tree of module files
+
+use std::path::{Path, PathBuf};
+use syn::{ext::IdentExt, AttrStyle, File, Item};
+
+/// Moves every inline module of `file` into a file of its own.
+///
+/// `root` is the name of the crate root file (`lib.rs` or `mod.rs`). Returned paths are relative
+/// to the directory of the root file, the root file itself goes first. Submodules of `foo.rs`
+/// are placed in the `foo` directory, so the module tree stays the same as in the inline version.
+pub fn split(file: File, root: &Path) -> Vec<(PathBuf, File)> {
+    let mut files = Vec::new();
+    split_file(file, root.to_path_buf(), PathBuf::new(), &mut files);
+    files
+}
+
+fn split_file(mut file: File, path: PathBuf, dir: PathBuf, files: &mut Vec<(PathBuf, File)>) {
+    let mut modules = Vec::new();
+    for item in &mut file.items {
+        if let Item::Mod(m) = item {
+            if let Some((_, items)) = m.content.take() {
+                m.semi = Some(Default::default());
+                // inner attributes go to the top of the new file
+                let (attrs, outer): (Vec<_>, Vec<_>) = m
+                    .attrs
+                    .drain(..)
+                    .partition(|a| matches!(a.style, AttrStyle::Inner(_)));
+                m.attrs = outer;
+                modules.push((
+                    m.ident.unraw().to_string(),
+                    File {
+                        shebang: None,
+                        attrs,
+                        items,
+                    },
+                ));
+            }
+        }
+    }
+    files.push((path, file));
+    for (name, module) in modules {
+        split_file(
+            module,
+            dir.join(format!("{name}.rs")),
+            dir.join(&name),
+            files,
+        );
+    }
+}
+
+#[test]
+fn split_nested() {
+    let file: File = syn::parse_quote! {
+        #![no_std]
+        #[doc = "A"]
+        pub mod a {
+            #![allow(unused)]
+            pub mod b {
+                pub struct B;
+            }
+        }
+        pub mod c;
+    };
+    let files = split(file, Path::new("lib.rs"));
+    let paths: Vec<_> = files.iter().map(|(p, _)| p.clone()).collect();
+    assert_eq!(
+        paths,
+        [
+            PathBuf::from("lib.rs"),
+            PathBuf::from("a.rs"),
+            PathBuf::from("a/b.rs")
+        ]
+    );
+    let (_, a) = &files[1];
+    assert_eq!(a.attrs.len(), 1);
+    assert!(matches!(&a.items[0], Item::Mod(m) if m.content.is_none()));
+}
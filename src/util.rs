@@ -15,7 +15,7 @@ use syn::{
     punctuated::Punctuated, token::PathSep, Lit, LitInt, PathArguments, PathSegment, Type, TypePath,
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 
 pub const BITS_PER_BYTE: u32 = 8;
 
@@ -229,6 +229,13 @@ pub fn digit_or_hex(n: u64) -> LitInt {
     }
 }
 
+/// `size` bytes at `address` are in the Cortex-M SRAM or peripheral bit-band region
+pub fn in_bitband_region(address: u64, size: u64) -> bool {
+    let region = address & !0x000F_FFFF;
+    matches!(region, 0x2000_0000 | 0x4000_0000)
+        && (address + size.max(1) - 1) & !0x000F_FFFF == region
+}
+
 /// Turns `n` into an unsuffixed separated hex token
 pub fn hex(n: u64) -> LitInt {
     let (h4, h3, h2, h1) = (
@@ -422,6 +429,55 @@ pub fn build_rs() -> TokenStream {
     }
 }
 
+/// Parses generated code and pretty-prints it
+pub fn format_code(tokens: TokenStream) -> Result<String> {
+    let file = syn::parse2(tokens).context("Error parsing generated code")?;
+    Ok(prettyplease::unparse(&file))
+}
+
+/// Checks if `name` matches glob `pattern`.
+///
+/// Supported wildcards are `*`, `?` and `[...]` character classes (`[!...]` for negation).
+/// Several patterns can be separated with commas.
+pub fn matches_glob(pattern: &str, name: &str) -> bool {
+    pattern
+        .split(',')
+        .any(|p| glob_match(p.trim().as_bytes(), name.as_bytes()))
+}
+
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| glob_match(rest, &name[i..])),
+        Some((b'?', rest)) => !name.is_empty() && glob_match(rest, &name[1..]),
+        Some((b'[', rest)) => {
+            let Some(end) = rest.iter().skip(1).position(|&c| c == b']').map(|e| e + 1) else {
+                return name.first() == Some(&b'[') && glob_match(rest, &name[1..]);
+            };
+            let Some((&c, name_rest)) = name.split_first() else {
+                return false;
+            };
+            let (negate, class) = match rest[..end].split_first() {
+                Some((b'!' | b'^', class)) => (true, class),
+                _ => (false, &rest[..end]),
+            };
+            let mut found = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == b'-' {
+                    found |= (class[i]..=class[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    found |= class[i] == c;
+                    i += 1;
+                }
+            }
+            found != negate && glob_match(&rest[end + 1..], name_rest)
+        }
+        Some((&c, rest)) => name.first() == Some(&c) && glob_match(rest, &name[1..]),
+    }
+}
+
 pub trait DimSuffix {
     fn expand_dim(&self, suffix: &str) -> Cow<str>;
     fn remove_dim(&self) -> Cow<str> {
@@ -500,3 +556,15 @@ fn pascalcase() {
     assert_eq!(to_pascal_case("FOO_BAR_1_2"), "FooBar1_2");
     assert_eq!(to_pascal_case("FOO_BAR_1_2_"), "FooBar1_2_");
 }
+
+#[test]
+fn glob() {
+    assert!(matches_glob("UART*", "UART1"));
+    assert!(matches_glob("UART*", "UART"));
+    assert!(!matches_glob("UART?", "UART"));
+    assert!(matches_glob("TIM[1-3]", "TIM2"));
+    assert!(!matches_glob("TIM[!1-3]", "TIM2"));
+    assert!(matches_glob("GPIOA,GPIOB", "GPIOB"));
+    assert!(matches_glob("*_CR", "CH1_CR"));
+    assert!(!matches_glob("CR", "CR1"));
+}